@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tic_tac_toe::analysis::best_move;
+use tic_tac_toe::board::{board_from_moves, get_winner, next_mark, winner_after_move, winning_lines, Rules, Square};
+
+/// Returns a full board of the given size that nobody has won, so that every line has to be checked.
+fn full_drawn_board(size: usize) -> Vec<Square> {
+    (0..size * size)
+        .map(|i| if ((i % size) / 2 + i / size).is_multiple_of(2) { Square::X } else { Square::O })
+        .collect()
+}
+
+fn bench_get_winner(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_winner");
+    for &size in [3, 5, 8].iter() {
+        let rules = Rules { size, win_length: size, ..Rules::default() };
+        let lines = winning_lines(&rules);
+        let squares = full_drawn_board(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &squares, |b, squares| {
+            b.iter(|| get_winner(black_box(squares), &lines))
+        });
+    }
+    group.finish();
+}
+
+fn bench_winner_after_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("winner_after_move");
+    for &size in [3, 5, 8].iter() {
+        let rules = Rules { size, win_length: size, ..Rules::default() };
+        let lines = winning_lines(&rules);
+        let squares = full_drawn_board(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &squares, |b, squares| {
+            b.iter(|| winner_after_move(black_box(squares), &lines, black_box(0)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_best_move(c: &mut Criterion) {
+    let rules = Rules::default();
+    let empty = vec![Square::Empty; rules.squares()];
+    let opening = [4, 0, 8, 2, 1, 7];
+    let mut group = c.benchmark_group("best_move");
+    // The depth is the number of empty squares left to search through.
+    for &played in [0, 2, 4].iter() {
+        let squares = board_from_moves(&empty, Square::X, &opening[..played]);
+        let depth = rules.squares() - played;
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &squares, |b, squares| {
+            b.iter(|| best_move(black_box(squares), next_mark(squares), &rules))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_winner, bench_winner_after_move, bench_best_move);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Plays a game with the library alone, without SDL or a clock, the way a browser front end would drive it: the
+//! computer's search is given a budget of positions rather than of time, and randomness comes from a seeded generator.
+//!
+//! Run it with `cargo run --example headless`, optionally followed by a seed.
+
+use std::cell::Cell;
+use tic_tac_toe::analysis::best_move_until;
+use tic_tac_toe::board::{get_winner, legal_moves, next_mark, winning_lines, Rules, Square};
+use tic_tac_toe::notation::square_name;
+use tic_tac_toe::rng::Rng;
+
+/// The most positions the computer searches for each of its moves.
+const SEARCH_BUDGET: u32 = 20_000;
+
+fn main() {
+    let seed = std::env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(1);
+    let mut rng = Rng::new(seed);
+    let rules = Rules { size: 4, win_length: 3, ..Rules::default() };
+    let lines = winning_lines(&rules);
+    let mut squares = vec![Square::Empty; rules.squares()];
+
+    // X plays at random and O searches, until somebody wins or the board is full.
+    loop {
+        let legal = legal_moves(&squares, &lines);
+        if legal.is_empty() {
+            break;
+        }
+        let to_move = next_mark(&squares);
+        let index = if to_move == Square::X {
+            legal[rng.below(legal.len())]
+        } else {
+            let searched = Cell::new(0);
+            let stop = || {
+                searched.set(searched.get() + 1);
+                searched.get() > SEARCH_BUDGET
+            };
+            best_move_until(&squares, to_move, &rules, &stop).unwrap_or(legal[0])
+        };
+        squares[index] = to_move;
+        println!("{:?} plays {}", to_move, square_name(index, rules.size));
+    }
+
+    for row in squares.chunks(rules.size) {
+        let row: String = row.iter().map(|square| match square {
+            Square::X => 'X',
+            Square::O => 'O',
+            Square::Empty => '.',
+        }).collect();
+        println!("{}", row);
+    }
+    match get_winner(&squares, &lines) {
+        Some(winner) => println!("{:?} wins", winner),
+        None => println!("Draw"),
+    }
+}
@@ -0,0 +1,71 @@
+//! Small goals a player can reach during a session, each announced the first time it is reached.
+
+use std::time::Duration;
+use tic_tac_toe::analysis::fork_moves;
+use tic_tac_toe::board::{Line, Square};
+
+/// The time a game must be won within for the quick win achievement, in seconds.
+const QUICK_WIN_SECS: u64 = 10;
+
+/// What an achievement is checked against once a game has been won.
+pub struct WonGame<'a> {
+    pub start: &'a [Square],
+    pub first: Square,
+    pub moves: &'a [usize],
+    pub lines: &'a [Line],
+    pub winner: Square,
+    /// The time from the first move to the end of the game, if a move was made in this session.
+    pub duration: Option<Duration>,
+    /// The games the loser has won in the match, if the game won a match.
+    pub match_won: Option<u32>,
+}
+
+/// A goal reached by winning a game in some way.
+struct Achievement {
+    name: &'static str,
+    reached: fn(&WonGame) -> bool,
+}
+
+/// Every achievement, in the order they are announced when one game reaches several.
+const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement { name: "first win", reached: |_| true },
+    Achievement { name: "flawless match", reached: |game| game.match_won == Some(0) },
+    Achievement { name: "won with a fork", reached: won_with_fork },
+    Achievement {
+        name: "quick win",
+        reached: |game| game.duration.is_some_and(|duration| duration < Duration::from_secs(QUICK_WIN_SECS)),
+    },
+];
+
+/// Returns whether the winner played a fork on the way to winning: a move that made two threats at once.
+fn won_with_fork(game: &WonGame) -> bool {
+    let mut squares = game.start.to_vec();
+    let mut mark = game.first;
+    for &index in game.moves {
+        if mark == game.winner && fork_moves(&squares, game.lines, mark).contains(&index) {
+            return true;
+        }
+        squares[index] = mark;
+        mark = mark.opponent();
+    }
+    false
+}
+
+/// The achievements reached so far in the session.
+#[derive(Default)]
+pub struct Achievements {
+    reached: Vec<&'static str>,
+}
+
+impl Achievements {
+    /// Returns the names of the achievements the game reaches for the first time in the session.
+    pub fn check(&mut self, game: &WonGame) -> Vec<&'static str> {
+        let new: Vec<&'static str> = ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| !self.reached.contains(&achievement.name) && (achievement.reached)(game))
+            .map(|achievement| achievement.name)
+            .collect();
+        self.reached.extend(&new);
+        new
+    }
+}
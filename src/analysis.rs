@@ -0,0 +1,529 @@
+use crate::board::{get_winner, legal_moves, winning_lines, Line, Rules, Square};
+use crate::rng::Rng;
+use std::collections::HashMap;
+// The clock is only read by the searches given a time budget, which are left out on wasm, where there is no clock.
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+/// The maximum number of distinct positions a single search will evaluate before giving up.
+const MAX_SEARCH_NODES: usize = 2_000_000;
+
+/// The maximum number of distinct positions counted through when sizing a game tree.
+const MAX_TREE_NODES: usize = 200_000;
+
+/// The result of a position under optimal play from both sides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The given player can force a win.
+    WinFor(Square),
+    /// Neither player can force a win.
+    Draw,
+    /// The search ran out of budget, or depth, before the position could be resolved.
+    Ongoing,
+}
+
+/// Why a move was a good one to play, as far as the lines on the board show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveReason {
+    /// The move completes a line.
+    Win,
+    /// The move takes a square the other player could have won in.
+    Block,
+    /// The move makes two threats at once.
+    Fork,
+    /// Nothing on the board called for the move right away.
+    Positional,
+}
+
+/// Memoized minimax search over board positions.
+struct Search<'a> {
+    lines: Vec<Line>,
+    /// Returns whether to give up, such as once nobody wants the answer any more.
+    stop: &'a dyn Fn() -> bool,
+    memo: HashMap<(Vec<Square>, Square), Outcome>,
+}
+
+impl Search<'_> {
+    /// Returns the outcome of the position, or None if the node budget was exhausted or the search was told to stop.
+    fn classify(&mut self, squares: &mut Vec<Square>, to_move: Square) -> Option<Outcome> {
+        if let Some(winner) = get_winner(squares, &self.lines) {
+            return Some(Outcome::WinFor(winner));
+        }
+        if !squares.contains(&Square::Empty) {
+            return Some(Outcome::Draw);
+        }
+        let key = (squares.clone(), to_move);
+        if let Some(outcome) = self.memo.get(&key) {
+            return Some(*outcome);
+        }
+        if self.memo.len() >= MAX_SEARCH_NODES || (self.stop)() {
+            return None;
+        }
+
+        let mut best = Outcome::WinFor(to_move.opponent());
+        for i in 0..squares.len() {
+            if squares[i] != Square::Empty {
+                continue;
+            }
+            squares[i] = to_move;
+            let outcome = self.classify(squares, to_move.opponent());
+            squares[i] = Square::Empty;
+            match outcome? {
+                Outcome::WinFor(winner) if winner == to_move => {
+                    best = Outcome::WinFor(to_move);
+                    break;
+                },
+                Outcome::Draw => best = Outcome::Draw,
+                _ => {},
+            }
+        }
+        self.memo.insert(key, best);
+        Some(best)
+    }
+}
+
+/// Counts the ways positions can be played out to the end, remembering the count for each position.
+struct TreeCount {
+    lines: Vec<Line>,
+    memo: HashMap<(Vec<Square>, Square), u64>,
+}
+
+impl TreeCount {
+    /// Returns the number of different games that can be played from the position, or None if there are too many to
+    /// count.
+    fn count(&mut self, squares: &mut Vec<Square>, to_move: Square) -> Option<u64> {
+        let legal = legal_moves(squares, &self.lines);
+        if legal.is_empty() {
+            return Some(1);
+        }
+        let key = (squares.clone(), to_move);
+        if let Some(count) = self.memo.get(&key) {
+            return Some(*count);
+        }
+        if self.memo.len() >= MAX_TREE_NODES {
+            return None;
+        }
+
+        let mut total: u64 = 0;
+        for index in legal {
+            squares[index] = to_move;
+            let count = self.count(squares, to_move.opponent());
+            squares[index] = Square::Empty;
+            total = total.checked_add(count?)?;
+        }
+        self.memo.insert(key, total);
+        Some(total)
+    }
+}
+
+/// Minimax search that looks a limited number of moves ahead and gives up once it is told to stop.
+struct TimedSearch<'a> {
+    lines: Vec<Line>,
+    /// Returns whether to give up, such as once a deadline passes.
+    stop: &'a dyn Fn() -> bool,
+    /// Only resolved outcomes are kept, since those hold no matter how deep the search that found them.
+    memo: HashMap<(Vec<Square>, Square), Outcome>,
+}
+
+impl TimedSearch<'_> {
+    /// Returns the outcome of the position looking the given number of moves ahead, which is Ongoing if that is not
+    /// far enough to resolve it, or None if the search was told to stop.
+    fn classify(&mut self, squares: &mut Vec<Square>, to_move: Square, depth: usize) -> Option<Outcome> {
+        if let Some(winner) = get_winner(squares, &self.lines) {
+            return Some(Outcome::WinFor(winner));
+        }
+        if !squares.contains(&Square::Empty) {
+            return Some(Outcome::Draw);
+        }
+        let key = (squares.clone(), to_move);
+        if let Some(outcome) = self.memo.get(&key) {
+            return Some(*outcome);
+        }
+        if (self.stop)() {
+            return None;
+        }
+        if depth == 0 {
+            return Some(Outcome::Ongoing);
+        }
+
+        // Unless a win is found, an unresolved reply leaves the whole position unresolved.
+        let mut best = Outcome::WinFor(to_move.opponent());
+        let mut unresolved = false;
+        for i in 0..squares.len() {
+            if squares[i] != Square::Empty {
+                continue;
+            }
+            squares[i] = to_move;
+            let outcome = self.classify(squares, to_move.opponent(), depth - 1);
+            squares[i] = Square::Empty;
+            match outcome? {
+                Outcome::WinFor(winner) if winner == to_move => {
+                    best = Outcome::WinFor(to_move);
+                    unresolved = false;
+                    break;
+                },
+                Outcome::Draw => best = Outcome::Draw,
+                Outcome::Ongoing => unresolved = true,
+                Outcome::WinFor(_) => {},
+            }
+        }
+        if unresolved {
+            return Some(Outcome::Ongoing);
+        }
+        self.memo.insert(key, best);
+        Some(best)
+    }
+}
+
+impl Outcome {
+    /// Ranks the outcome from the point of view of the given player, where higher is better.
+    fn rank(self, player: Square) -> u8 {
+        match self {
+            Outcome::WinFor(winner) if winner == player => 3,
+            Outcome::Draw => 2,
+            Outcome::Ongoing => 1,
+            Outcome::WinFor(_) => 0,
+        }
+    }
+}
+
+/// Returns the empty squares of the line the given player is closest to completing, and how many moves away that is,
+/// or None if the other player has a mark in every line. A line that is already complete is zero moves away.
+pub fn moves_to_complete(squares: &[Square], lines: &[Line], player: Square) -> Option<(Vec<usize>, usize)> {
+    lines
+        .iter()
+        .filter(|line| line.iter().all(|&i| squares[i] != player.opponent()))
+        .map(|line| line.iter().copied().filter(|&i| squares[i] == Square::Empty).collect::<Vec<_>>())
+        .min_by_key(|empty| empty.len())
+        .map(|empty| {
+            let moves = empty.len();
+            (empty, moves)
+        })
+}
+
+/// Returns the squares the given player could win in on their next move, each listed once.
+pub fn threats(squares: &[Square], lines: &[Line], player: Square) -> Vec<usize> {
+    let mut threats = Vec::new();
+    for line in lines {
+        if let Some((empty, 1)) = moves_to_complete(squares, std::slice::from_ref(line), player) {
+            if !threats.contains(&empty[0]) {
+                threats.push(empty[0]);
+            }
+        }
+    }
+    threats
+}
+
+/// Returns the squares the given player could play in to make a fork: two or more threats at once, so that the other
+/// player cannot block them all. Moves that win on the spot are not forks.
+pub fn fork_moves(squares: &[Square], lines: &[Line], player: Square) -> Vec<usize> {
+    let mut squares = squares.to_vec();
+    legal_moves(&squares, lines)
+        .into_iter()
+        .filter(|&index| {
+            squares[index] = player;
+            let fork = get_winner(&squares, lines).is_none() && threats(&squares, lines, player).len() >= 2;
+            squares[index] = Square::Empty;
+            fork
+        })
+        .collect()
+}
+
+/// Returns why the given player would play in the square, judged from the board before the move. Winning comes before
+/// blocking, and blocking before forking, since a move that does more than one is played for the more urgent reason.
+pub fn move_reason(squares: &[Square], lines: &[Line], player: Square, index: usize) -> MoveReason {
+    if threats(squares, lines, player).contains(&index) {
+        MoveReason::Win
+    } else if threats(squares, lines, player.opponent()).contains(&index) {
+        MoveReason::Block
+    } else if fork_moves(squares, lines, player).contains(&index) {
+        MoveReason::Fork
+    } else {
+        MoveReason::Positional
+    }
+}
+
+/// Returns a legal move picked at random with the given chance, from 0.0 to 1.0, for a player that is meant to make
+/// mistakes, or None if the best move should be played instead. Nothing is drawn from the generator when the chance is
+/// zero, so a player that never blunders leaves it as it was.
+pub fn blunder(squares: &[Square], lines: &[Line], rate: f64, rng: &mut Rng) -> Option<usize> {
+    if rate <= 0.0 || rng.next_f64() >= rate {
+        return None;
+    }
+    let legal = legal_moves(squares, lines);
+    if legal.is_empty() { None } else { Some(legal[rng.below(legal.len())]) }
+}
+
+/// Returns the number of different games that can be played from the position to the end, or None if the tree is too
+/// big to count. A game that is already over counts as one.
+pub fn game_tree_size(squares: &[Square], to_move: Square, rules: &Rules) -> Option<u64> {
+    let mut search = TreeCount { lines: winning_lines(rules), memo: HashMap::new() };
+    search.count(&mut squares.to_vec(), to_move)
+}
+
+/// Returns the outcome of the position with the given player to move, assuming optimal play from both sides.
+pub fn classify_position(squares: &[Square], to_move: Square, rules: &Rules) -> Outcome {
+    classify_position_until(squares, to_move, rules, &|| false)
+}
+
+/// Returns the outcome of the position like `classify_position`, but gives up with Ongoing as soon as the given
+/// function says to stop. It is asked before each position searched.
+pub fn classify_position_until(squares: &[Square], to_move: Square, rules: &Rules, stop: &dyn Fn() -> bool) -> Outcome {
+    let mut search = Search { lines: winning_lines(rules), stop, memo: HashMap::new() };
+    search.classify(&mut squares.to_vec(), to_move).unwrap_or(Outcome::Ongoing)
+}
+
+/// Returns each square the given player could play in, with the outcome of the position after playing there.
+/// Every move is searched with the same memo, so positions reached by more than one move are only solved once.
+pub fn evaluate_moves(squares: &[Square], to_move: Square, rules: &Rules) -> Vec<(usize, Outcome)> {
+    let mut search = Search { lines: winning_lines(rules), stop: &|| false, memo: HashMap::new() };
+    let mut board = squares.to_vec();
+    legal_moves(squares, &search.lines)
+        .into_iter()
+        .map(|i| {
+            board[i] = to_move;
+            let outcome = search.classify(&mut board, to_move.opponent()).unwrap_or(Outcome::Ongoing);
+            board[i] = Square::Empty;
+            (i, outcome)
+        })
+        .collect()
+}
+
+/// Returns the square the given player should play to get the best outcome, or None if the game is over.
+/// A move that wins on the spot is always preferred, so that a won game is not dragged out.
+pub fn best_move(squares: &[Square], to_move: Square, rules: &Rules) -> Option<usize> {
+    let mut search = Search { lines: winning_lines(rules), stop: &|| false, memo: HashMap::new() };
+    let mut board = squares.to_vec();
+    let mut best: Option<(usize, u8)> = None;
+    for i in legal_moves(squares, &search.lines) {
+        board[i] = to_move;
+        if get_winner(&board, &search.lines) == Some(to_move) {
+            return Some(i);
+        }
+        let rank = search.classify(&mut board, to_move.opponent()).unwrap_or(Outcome::Ongoing).rank(to_move);
+        board[i] = Square::Empty;
+        match best {
+            Some((_, best_rank)) if best_rank >= rank => {},
+            _ => best = Some((i, rank)),
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+/// Returns the square the given player should play, searching one move deeper at a time until the position is
+/// resolved or the time budget runs out, or None if the game is over. When time runs out the best move of the deepest
+/// finished search is played, so the AI stays responsive on large boards while being as strong as time allows.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn best_move_within(squares: &[Square], to_move: Square, rules: &Rules, budget: Duration) -> Option<usize> {
+    best_move_cancellable(squares, to_move, rules, budget, &AtomicBool::new(false))
+}
+
+/// Returns the square the given player should play, like `best_move_within`, but also stops searching as soon as the
+/// flag is set from another thread. A cancelled search still returns the best move found so far.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn best_move_cancellable(squares: &[Square], to_move: Square, rules: &Rules, budget: Duration, cancel: &AtomicBool) -> Option<usize> {
+    let deadline = Instant::now() + budget;
+    best_move_until(squares, to_move, rules, &|| Instant::now() >= deadline || cancel.load(Ordering::Relaxed))
+}
+
+/// Returns the square the given player should play, like `best_move_within`, but searches until the given function
+/// says to stop rather than for a time, so that it can run without a clock. It is asked before each position searched.
+pub fn best_move_until(squares: &[Square], to_move: Square, rules: &Rules, stop: &dyn Fn() -> bool) -> Option<usize> {
+    let mut search = TimedSearch { lines: winning_lines(rules), stop, memo: HashMap::new() };
+    let legal = legal_moves(squares, &search.lines);
+    let mut board = squares.to_vec();
+    for &i in &legal {
+        board[i] = to_move;
+        let wins = get_winner(&board, &search.lines) == Some(to_move);
+        board[i] = Square::Empty;
+        if wins {
+            return Some(i);
+        }
+    }
+
+    // Until a search finishes, any legal move is better than none.
+    let mut best = legal.first().copied();
+    for depth in 1..=legal.len() {
+        let mut deepest: Option<(usize, u8)> = None;
+        let mut resolved = true;
+        for &i in &legal {
+            board[i] = to_move;
+            let outcome = search.classify(&mut board, to_move.opponent(), depth - 1);
+            board[i] = Square::Empty;
+            let outcome = match outcome {
+                Some(outcome) => outcome,
+                None => return best,
+            };
+            resolved &= outcome != Outcome::Ongoing;
+            let rank = outcome.rank(to_move);
+            match deepest {
+                Some((_, best_rank)) if best_rank >= rank => {},
+                _ => deepest = Some((i, rank)),
+            }
+        }
+        best = deepest.map(|(i, _)| i);
+        if resolved {
+            break;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board;
+
+    /// Returns the outcome of the 3x3 board written row by row.
+    fn classify(board: &str, to_move: Square) -> Outcome {
+        classify_position(&parse_board(board, 3).unwrap(), to_move, &Rules::default())
+    }
+
+    #[test]
+    fn the_empty_board_is_a_draw() {
+        assert_eq!(classify(".........", Square::X), Outcome::Draw);
+    }
+
+    #[test]
+    fn answering_the_center_with_an_edge_loses() {
+        assert_eq!(classify(".O..X....", Square::X), Outcome::WinFor(Square::X));
+        assert_eq!(classify("O...X....", Square::X), Outcome::Draw);
+    }
+
+    #[test]
+    fn answering_a_corner_anywhere_but_the_center_loses() {
+        assert_eq!(classify("X...O....", Square::X), Outcome::Draw);
+        assert_eq!(classify("XO.......", Square::X), Outcome::WinFor(Square::X));
+        assert_eq!(classify("X.O......", Square::X), Outcome::WinFor(Square::X));
+        assert_eq!(classify("X.......O", Square::X), Outcome::WinFor(Square::X));
+    }
+
+    /// Returns the 3x3 board written row by row, with its winning lines.
+    fn board(text: &str) -> (Vec<Square>, Vec<Line>) {
+        (parse_board(text, 3).unwrap(), winning_lines(&Rules::default()))
+    }
+
+    #[test]
+    fn a_line_one_move_from_done_is_the_closest() {
+        let (squares, lines) = board("XX.OO....");
+        assert_eq!(moves_to_complete(&squares, &lines, Square::X), Some((vec![2], 1)));
+        assert_eq!(moves_to_complete(&squares, &lines, Square::O), Some((vec![5], 1)));
+        let (squares, lines) = board("XXXOO....");
+        assert_eq!(moves_to_complete(&squares, &lines, Square::X), Some((vec![], 0)));
+    }
+
+    #[test]
+    fn a_fork_leaves_two_lines_one_move_away() {
+        let (squares, lines) = board("X.X...XOO");
+        assert_eq!(moves_to_complete(&squares, &lines, Square::X).map(|(_, moves)| moves), Some(1));
+        let mut found = threats(&squares, &lines, Square::X);
+        found.sort();
+        assert_eq!(found, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn opposite_corners_against_the_center_fork_in_the_third_corner() {
+        let (squares, lines) = board("XO..O...X");
+        assert_eq!(fork_moves(&squares, &lines, Square::X), vec![6]);
+    }
+
+    #[test]
+    fn a_corner_and_the_center_fork_against_an_edge() {
+        let (squares, lines) = board("XO..X...O");
+        let mut forks = fork_moves(&squares, &lines, Square::X);
+        forks.sort();
+        assert_eq!(forks, vec![3, 6]);
+        assert_eq!(fork_moves(&squares, &lines, Square::O), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn winning_moves_are_not_forks() {
+        let (squares, lines) = board("XX.XOO...");
+        assert!(!fork_moves(&squares, &lines, Square::X).contains(&2));
+        assert!(!fork_moves(&squares, &lines, Square::X).contains(&6));
+    }
+
+    #[test]
+    fn no_line_is_left_once_every_one_is_blocked() {
+        let (squares, lines) = board("XOXXOOOXX");
+        assert_eq!(moves_to_complete(&squares, &lines, Square::X), None);
+        assert_eq!(moves_to_complete(&squares, &lines, Square::O), None);
+    }
+
+    #[test]
+    fn a_blunder_rate_of_zero_never_blunders_or_draws_from_the_generator() {
+        let (squares, lines) = board("X...O....");
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            assert_eq!(blunder(&squares, &lines, 0.0, &mut rng), None);
+        }
+        assert_eq!(rng, Rng::new(7));
+    }
+
+    #[test]
+    fn a_blunder_rate_of_one_always_plays_a_random_legal_move() {
+        let (squares, lines) = board("X...O....");
+        let (mut rng, mut again) = (Rng::new(7), Rng::new(7));
+        let mut played = Vec::new();
+        for _ in 0..100 {
+            let index = blunder(&squares, &lines, 1.0, &mut rng).unwrap();
+            assert_eq!(squares[index], Square::Empty);
+            assert_eq!(blunder(&squares, &lines, 1.0, &mut again), Some(index));
+            played.push(index);
+        }
+        played.sort();
+        played.dedup();
+        assert_eq!(played, vec![1, 2, 3, 5, 6, 7, 8]);
+        assert_eq!(blunder(&parse_board("XXXOO....", 3).unwrap(), &lines, 1.0, &mut rng), None);
+    }
+
+    #[test]
+    fn timed_searches_stop_near_the_budget_with_a_legal_move() {
+        let rules = Rules { size: 7, win_length: 4, ..Rules::default() };
+        let mut squares = vec![Square::Empty; rules.squares()];
+        squares[24] = Square::X;
+        let budget = Duration::from_millis(100);
+        let started = Instant::now();
+        let found = best_move_within(&squares, Square::O, &rules, budget).unwrap();
+        assert!(started.elapsed() < budget * 5, "took {:?}", started.elapsed());
+        assert_eq!(squares[found], Square::Empty);
+    }
+
+    #[test]
+    fn a_cancelled_search_still_plays_a_legal_move() {
+        let rules = Rules { size: 7, win_length: 4, ..Rules::default() };
+        let squares = vec![Square::Empty; rules.squares()];
+        let found = best_move_cancellable(&squares, Square::X, &rules, Duration::from_secs(60), &AtomicBool::new(true));
+        assert!(found.is_some_and(|index| index < squares.len()));
+    }
+
+    #[test]
+    fn a_search_wins_on_the_spot_when_it_can() {
+        let squares = parse_board("XX.OO....", 3).unwrap();
+        assert_eq!(best_move_within(&squares, Square::X, &Rules::default(), Duration::from_millis(50)), Some(2));
+        assert_eq!(best_move_within(&parse_board("XOXXOOOXX", 3).unwrap(), Square::O, &Rules::default(), Duration::from_millis(50)), None);
+    }
+
+    #[test]
+    fn a_stopped_classification_is_ongoing() {
+        let rules = Rules { size: 4, ..Rules::default() };
+        let squares = vec![Square::Empty; rules.squares()];
+        assert_eq!(classify_position_until(&squares, Square::X, &rules, &|| true), Outcome::Ongoing);
+        let (squares, _) = board("XX.OO....");
+        assert_eq!(classify_position_until(&squares, Square::X, &Rules::default(), &|| false), Outcome::WinFor(Square::X));
+    }
+
+    #[test]
+    fn finished_boards_are_classified_as_they_stand() {
+        assert_eq!(classify("XXXOO....", Square::O), Outcome::WinFor(Square::X));
+        assert_eq!(classify("XOXXOOOXX", Square::O), Outcome::Draw);
+    }
+
+    #[test]
+    fn a_win_in_one_goes_to_the_player_to_move() {
+        assert_eq!(classify("XX.OO....", Square::X), Outcome::WinFor(Square::X));
+        assert_eq!(classify("XX.OO.X..", Square::O), Outcome::WinFor(Square::O));
+    }
+}
@@ -0,0 +1,507 @@
+use crate::rng::Rng;
+
+/// The number of squares in the horizontal and vertical direction when no other size is given.
+pub const DEFAULT_SIZE: usize = 3;
+
+/// A direction a line can run in, as the (row, column) step from one of its squares to the next.
+type Direction = (isize, isize);
+
+/// The directions of straight lines.
+const STRAIGHT_DIRECTIONS: [Direction; 2] = [(0, 1), (1, 0)];
+
+/// The direction of lines parallel to the top-left to bottom-right diagonal.
+const DIAGONAL_DIRECTION: Direction = (1, 1);
+
+/// The direction of lines parallel to the bottom-left to top-right diagonal.
+const ANTI_DIAGONAL_DIRECTION: Direction = (-1, 1);
+
+/// A list of square indices that wins the game when all are owned by the same player.
+pub type Line = Vec<usize>;
+
+/// The shape of the groups of squares that win the game when one player owns all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinCondition {
+    /// Any win_length squares in a row, in an allowed direction.
+    Line,
+    /// The four corners of the board.
+    Corners,
+    /// Any two by two block of squares.
+    Block,
+}
+
+/// A way of deciding a drawn game from the final board, for when a game has to have a winner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TieBreaker {
+    /// The player with more of the center wins: the middle square, or the middle two by two block on an even board.
+    Center,
+    /// The player with more of the squares around the center wins.
+    AroundCenter,
+}
+
+impl TieBreaker {
+    /// Returns the winner of a drawn game on a board of the given size, or None if the tie-breaker is tied too.
+    pub fn winner(self, squares: &[Square], size: usize) -> Option<Square> {
+        // The center is measured in half squares, so that the middle of an even board falls on a grid line.
+        let middle = size as isize - 1;
+        let distance = |index: usize| {
+            let row = (2 * (index / size) as isize - middle).abs();
+            let col = (2 * (index % size) as isize - middle).abs();
+            // Squares in the center are zero steps out, and their neighbours one step.
+            row.max(col) as usize / 2
+        };
+        let wanted = match self {
+            TieBreaker::Center => 0,
+            TieBreaker::AroundCenter => 1,
+        };
+        let (mut x, mut o) = (0, 0);
+        for (index, &square) in squares.iter().enumerate() {
+            if distance(index) == wanted {
+                match square {
+                    Square::X => x += 1,
+                    Square::O => o += 1,
+                    Square::Empty => {},
+                }
+            }
+        }
+        match x.cmp(&o) {
+            std::cmp::Ordering::Greater => Some(Square::X),
+            std::cmp::Ordering::Less => Some(Square::O),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+/// The rules that decide the shape of the board and which lines count as a win.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rules {
+    /// The number of squares in the horizontal and vertical direction.
+    pub size: usize,
+    /// The shape of the groups of squares that win.
+    pub win_condition: WinCondition,
+    /// The number of squares in a row needed to win, when winning with a line.
+    pub win_length: usize,
+    /// Whether the top-left to bottom-right diagonal wins.
+    pub diagonal: bool,
+    /// Whether the bottom-left to top-right diagonal wins.
+    pub anti_diagonal: bool,
+    /// Whether the board is made of hexagons. Each row is shifted half a cell to the right of the one above it, so the
+    /// anti-diagonal follows a hex axis while the diagonal does not, and never wins.
+    pub hex: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_SIZE,
+            win_condition: WinCondition::Line,
+            win_length: DEFAULT_SIZE,
+            diagonal: true,
+            anti_diagonal: true,
+            hex: false,
+        }
+    }
+}
+
+impl Rules {
+    /// Returns the number of squares on the board.
+    pub fn squares(&self) -> usize {
+        self.size * self.size
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Square { X, O, Empty }
+
+impl Square {
+    /// Returns the mark of the other player, or Empty for Empty.
+    pub fn opponent(self) -> Square {
+        match self {
+            Square::X => Square::O,
+            Square::O => Square::X,
+            Square::Empty => Square::Empty,
+        }
+    }
+}
+
+/// Returns every group of squares that wins the game under the given rules. These are all called lines, even when the
+/// win condition makes them other shapes.
+pub fn winning_lines(rules: &Rules) -> Vec<Line> {
+    let n = rules.size;
+    match rules.win_condition {
+        WinCondition::Line => straight_lines(rules),
+        WinCondition::Corners => vec![vec![0, n - 1, n * (n - 1), (n * n) - 1]],
+        WinCondition::Block => (0..n.saturating_sub(1))
+            .flat_map(|row| (0..n - 1).map(move |col| (row * n) + col))
+            .map(|i| vec![i, i + 1, i + n, i + n + 1])
+            .collect(),
+    }
+}
+
+/// Returns each run of win_length squares in an allowed direction.
+fn straight_lines(rules: &Rules) -> Vec<Line> {
+    let mut directions = STRAIGHT_DIRECTIONS.to_vec();
+    if rules.diagonal && !rules.hex {
+        directions.push(DIAGONAL_DIRECTION);
+    }
+    if rules.anti_diagonal {
+        directions.push(ANTI_DIAGONAL_DIRECTION);
+    }
+    let mut lines = Vec::new();
+    for &direction in &directions {
+        for row in 0..rules.size {
+            for col in 0..rules.size {
+                if let Some(line) = build_line(rules, row, col, direction) {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Returns the indices of the line of win_length squares that starts at the given square and runs in the given
+/// direction, or None if it would run off the board.
+fn build_line(rules: &Rules, row: usize, col: usize, direction: Direction) -> Option<Line> {
+    let size = rules.size as isize;
+    (0..rules.win_length as isize)
+        .map(|i| {
+            let row = row as isize + direction.0 * i;
+            let col = col as isize + direction.1 * i;
+            if row < 0 || col < 0 || row >= size || col >= size {
+                None
+            } else {
+                Some((row * size + col) as usize)
+            }
+        })
+        .collect()
+}
+
+/// Returns the winner of the board, or None if nobody has won yet.
+pub fn get_winner(squares: &[Square], lines: &[Line]) -> Option<Square> {
+    lines.iter().find_map(|line| line_winner(squares, line))
+}
+
+/// Returns the winner of the board like get_winner, but without looking through the lines while neither player has
+/// enough marks to fill even the shortest of them, as early in a game.
+pub fn is_won(squares: &[Square], lines: &[Line]) -> Option<Square> {
+    let shortest = lines.iter().map(Vec::len).min()?;
+    if count_marks(squares, Square::X).max(count_marks(squares, Square::O)) < shortest { None } else { get_winner(squares, lines) }
+}
+
+/// Returns whether nothing more can be played on the board, because somebody has won or every square is taken.
+pub fn game_over(squares: &[Square], lines: &[Line]) -> bool {
+    !squares.contains(&Square::Empty) || is_won(squares, lines).is_some()
+}
+
+/// Returns the indices of the squares the player to move can play in, which is none once somebody has won.
+pub fn legal_moves(squares: &[Square], lines: &[Line]) -> Vec<usize> {
+    if get_winner(squares, lines).is_some() {
+        return Vec::new();
+    }
+    (0..squares.len()).filter(|&i| squares[i] == Square::Empty).collect()
+}
+
+/// Returns the winner if the mark in the given square completed a line, checking only the lines through that square.
+pub fn winner_after_move(squares: &[Square], lines: &[Line], index: usize) -> Option<Square> {
+    lines.iter().filter(|line| line.contains(&index)).find_map(|line| line_winner(squares, line))
+}
+
+/// Returns the winner of the given line.
+fn line_winner(squares: &[Square], line: &[usize]) -> Option<Square> {
+    let line_square = squares[line[0]];
+    if line_square == Square::Empty {
+        return None;
+    }
+    if line.iter().all(|&i| squares[i] == line_square) {
+        Some(line_square)
+    } else {
+        None
+    }
+}
+
+/// Returns a board reached by random moves from the empty board, with about the given share of its squares filled, on
+/// which nobody has won and a move is left. Moves that would win are passed over, so the board may be less full than
+/// asked if every move left wins.
+pub fn random_board(rules: &Rules, rng: &mut Rng, fill_fraction: f64) -> Vec<Square> {
+    let lines = winning_lines(rules);
+    let mut squares = vec![Square::Empty; rules.squares()];
+    let moves = ((fill_fraction.clamp(0.0, 1.0) * squares.len() as f64).round() as usize).min(squares.len() - 1);
+    for _ in 0..moves {
+        let to_move = next_mark(&squares);
+        let mut empty: Vec<usize> = (0..squares.len()).filter(|&i| squares[i] == Square::Empty).collect();
+        // Empty squares are tried in a random order until one does not win.
+        let played = loop {
+            if empty.is_empty() {
+                break false;
+            }
+            let index = empty.swap_remove(rng.below(empty.len()));
+            squares[index] = to_move;
+            if winner_after_move(&squares, &lines, index).is_none() {
+                break true;
+            }
+            squares[index] = Square::Empty;
+        };
+        if !played {
+            break;
+        }
+    }
+    squares
+}
+
+/// Returns the number of squares holding the given mark.
+pub fn count_marks(squares: &[Square], mark: Square) -> usize {
+    squares.iter().filter(|&&square| square == mark).count()
+}
+
+/// Returns the mark whose turn it is, given that X always moves first.
+pub fn next_mark(squares: &[Square]) -> Square {
+    if count_marks(squares, Square::X) > count_marks(squares, Square::O) { Square::O } else { Square::X }
+}
+
+/// Returns whether the position could have come up in a game where X moves first and play stops at the first win.
+pub fn is_legal_position(squares: &[Square], to_move: Square, rules: &Rules) -> bool {
+    let x = count_marks(squares, Square::X);
+    let o = count_marks(squares, Square::O);
+    let counts_match = match to_move {
+        Square::X => x == o,
+        Square::O => x == o + 1,
+        Square::Empty => false,
+    };
+    if !counts_match {
+        return false;
+    }
+    // The winner must have made the last move, and nobody can have moved after it.
+    let lines = winning_lines(rules);
+    let won = |mark| lines.iter().any(|line| line.iter().all(|&i| squares[i] == mark));
+    match (won(Square::X), won(Square::O)) {
+        (true, true) => false,
+        (true, false) => to_move == Square::O,
+        (false, true) => to_move == Square::X,
+        (false, false) => true,
+    }
+}
+
+/// Parses a board of the given size written row by row with X, O, and . for an empty square, or None if it is malformed.
+pub fn parse_board(text: &str, size: usize) -> Option<Vec<Square>> {
+    let squares = text
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'X' => Some(Square::X),
+            'O' => Some(Square::O),
+            '.' => Some(Square::Empty),
+            _ => None,
+        })
+        .collect::<Option<Vec<Square>>>()?;
+    if squares.len() == size * size { Some(squares) } else { None }
+}
+
+/// Writes a board row by row with X, O, and . for an empty square, the inverse of parse_board().
+pub fn format_board(squares: &[Square]) -> String {
+    squares
+        .iter()
+        .map(|square| match square {
+            Square::X => 'X',
+            Square::O => 'O',
+            Square::Empty => '.',
+        })
+        .collect()
+}
+
+/// Returns a hash of the board that is the same on every run and every platform, unlike the standard library's hasher,
+/// so that it can be logged and compared between runs. It is FNV-1a over the number of squares and then each square.
+pub fn board_hash(squares: &[Square]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let size = (squares.len() as u64).to_le_bytes();
+    let marks = squares.iter().map(|square| match square {
+        Square::Empty => 0,
+        Square::X => 1,
+        Square::O => 2,
+    });
+    size.iter().copied().chain(marks).fold(OFFSET_BASIS, |hash, byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// Returns the board reached by playing the given square indices in order from the starting board, with the given
+/// player moving first.
+pub fn board_from_moves(start: &[Square], first: Square, moves: &[usize]) -> Vec<Square> {
+    let mut squares = start.to_vec();
+    let mut mark = first;
+    for &index in moves {
+        squares[index] = mark;
+        mark = mark.opponent();
+    }
+    squares
+}
+
+/// Returns a square value from the squares vector by treating it as a table with the given number of columns.
+pub fn get_square_flatten_index(squares: &[Square], size: usize, row: usize, col: usize) -> &Square {
+    &squares[(row * size) + col]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_won_agrees_with_get_winner() {
+        let rules = Rules { size: 4, win_length: 3, ..Rules::default() };
+        let lines = winning_lines(&rules);
+        let mut squares = vec![Square::Empty; rules.squares()];
+        for (index, mark) in [(0, Square::X), (5, Square::O), (1, Square::X), (6, Square::O), (4, Square::X), (7, Square::O)] {
+            squares[index] = mark;
+            assert_eq!(is_won(&squares, &lines), get_winner(&squares, &lines));
+        }
+        assert_eq!(is_won(&squares, &lines), Some(Square::O));
+    }
+
+    /// Returns who has won the board of the given size written row by row under the given rules.
+    fn winner(board: &str, rules: &Rules) -> Option<Square> {
+        get_winner(&parse_board(board, rules.size).unwrap(), &winning_lines(rules))
+    }
+
+    #[test]
+    fn diagonals_only_win_while_they_are_allowed() {
+        let rules = Rules::default();
+        let no_diagonals = Rules { diagonal: false, anti_diagonal: false, ..rules.clone() };
+        let no_anti_diagonal = Rules { anti_diagonal: false, ..rules.clone() };
+        assert_eq!(winner("X...X...X", &rules), Some(Square::X));
+        assert_eq!(winner("..X.X.X..", &rules), Some(Square::X));
+        assert_eq!(winner("X...X...X", &no_diagonals), None);
+        assert_eq!(winner("..X.X.X..", &no_diagonals), None);
+        assert_eq!(winner("X...X...X", &no_anti_diagonal), Some(Square::X));
+        assert_eq!(winner("..X.X.X..", &no_anti_diagonal), None);
+    }
+
+    #[test]
+    fn rows_and_columns_win_without_diagonals() {
+        let rules = Rules { size: 4, win_length: 4, diagonal: false, anti_diagonal: false, ..Rules::default() };
+        assert_eq!(winner("O...O...O...O...", &rules), Some(Square::O));
+        assert_eq!(winner("....XXXX........", &rules), Some(Square::X));
+        assert_eq!(winner("X....X....X....X", &rules), None);
+        assert_eq!(winner("...X..X..X..X...", &rules), None);
+    }
+
+    #[test]
+    fn positions_are_legal_when_the_counts_fit_the_player_to_move() {
+        let legal = |board: &str, to_move| is_legal_position(&parse_board(board, 3).unwrap(), to_move, &Rules::default());
+        assert!(legal(".........", Square::X));
+        assert!(legal("X........", Square::O));
+        assert!(legal("XO.......", Square::X));
+        assert!(!legal(".........", Square::O));
+        assert!(!legal("X........", Square::X));
+        assert!(!legal("XXX......", Square::O));
+        assert!(!legal("O........", Square::X));
+        assert!(!legal("X........", Square::Empty));
+    }
+
+    #[test]
+    fn positions_are_illegal_when_play_went_on_after_a_win() {
+        let legal = |board: &str, to_move| is_legal_position(&parse_board(board, 3).unwrap(), to_move, &Rules::default());
+        assert!(legal("XXXOO....", Square::O));
+        assert!(!legal("XXXOO.O..", Square::X));
+        assert!(legal("OOOXX.X..", Square::X));
+        assert!(!legal("XXXOOO...", Square::X));
+    }
+
+    #[test]
+    fn every_empty_square_is_legal_until_the_game_is_won() {
+        let lines = winning_lines(&Rules::default());
+        assert_eq!(legal_moves(&[Square::Empty; 9], &lines), (0..9).collect::<Vec<usize>>());
+        assert_eq!(legal_moves(&parse_board("XO.......", 3).unwrap(), &lines), (2..9).collect::<Vec<usize>>());
+        assert_eq!(legal_moves(&parse_board("XOXXOOOXX", 3).unwrap(), &lines), Vec::<usize>::new());
+        assert_eq!(legal_moves(&parse_board("XXXOO....", 3).unwrap(), &lines), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn the_four_corners_win_on_a_4x4_board() {
+        let rules = Rules { size: 4, win_length: 4, win_condition: WinCondition::Corners, ..Rules::default() };
+        assert_eq!(winner("X..X........X..X", &rules), Some(Square::X));
+        assert_eq!(winner("X..X........X..O", &rules), None);
+        assert_eq!(winner("XXXX............", &rules), None);
+    }
+
+    #[test]
+    fn any_2x2_block_wins_on_a_4x4_board() {
+        let rules = Rules { size: 4, win_length: 2, win_condition: WinCondition::Block, ..Rules::default() };
+        assert_eq!(winning_lines(&rules).len(), 9);
+        assert_eq!(winner("OO..OO..........", &rules), Some(Square::O));
+        assert_eq!(winner("..........XX..XX", &rules), Some(Square::X));
+        assert_eq!(winner(".XX..XX.........", &rules), Some(Square::X));
+        assert_eq!(winner("X.X.X.X.........", &rules), None);
+        assert_eq!(winner("XXXX............", &rules), None);
+    }
+
+    #[test]
+    fn the_center_breaks_a_tie_on_3x3() {
+        let board = |text| parse_board(text, 3).unwrap();
+        assert_eq!(TieBreaker::Center.winner(&board("XOXXOOOXX"), 3), Some(Square::O));
+        assert_eq!(TieBreaker::Center.winner(&board("XOXOXXOXO"), 3), Some(Square::X));
+        assert_eq!(TieBreaker::Center.winner(&board("XOX......"), 3), None);
+        assert_eq!(TieBreaker::AroundCenter.winner(&board("XOXXOOOXX"), 3), Some(Square::X));
+    }
+
+    #[test]
+    fn an_even_board_is_centered_on_its_middle_block() {
+        let board = parse_board("......XO..OX....", 4).unwrap();
+        assert_eq!(TieBreaker::Center.winner(&board, 4), None);
+        let board = parse_board(".....XO..OX.....", 4).unwrap();
+        assert_eq!(TieBreaker::Center.winner(&board, 4), None);
+        let board = parse_board(".....XXO..X.....", 4).unwrap();
+        assert_eq!(TieBreaker::Center.winner(&board, 4), Some(Square::X));
+    }
+
+    #[test]
+    fn equal_boards_hash_equal_and_different_ones_apart() {
+        assert_eq!(board_hash(&parse_board("XO..X...O", 3).unwrap()), board_hash(&parse_board("XO..X...O", 3).unwrap()));
+        let mut hashes = std::collections::HashSet::new();
+        for mut code in 0..3usize.pow(9) {
+            let squares: Vec<Square> = (0..9)
+                .map(|_| {
+                    let square = [Square::Empty, Square::X, Square::O][code % 3];
+                    code /= 3;
+                    square
+                })
+                .collect();
+            hashes.insert(board_hash(&squares));
+        }
+        assert_eq!(hashes.len(), 3usize.pow(9));
+        assert_ne!(board_hash(&[Square::Empty; 9]), board_hash(&[Square::Empty; 16]));
+    }
+
+    #[test]
+    fn board_hashes_are_the_same_on_every_run() {
+        // Worked out from the FNV-1a definition apart from this code, so that a change to the hash shows up here.
+        assert_eq!(board_hash(&[Square::Empty; 9]), 0x75dd_28ca_cbe1_14c4);
+        assert_eq!(board_hash(&parse_board("XO..X...O", 3).unwrap()), 0xbea3_0aeb_cd4e_08cc);
+    }
+
+    #[test]
+    fn random_boards_are_legal_and_not_won() {
+        let mut rng = Rng::new(3);
+        for rules in [Rules::default(), Rules { size: 4, win_length: 3, ..Rules::default() }, Rules { size: 5, ..Rules::default() }] {
+            let lines = winning_lines(&rules);
+            for &fill_fraction in &[0.0, 0.3, 0.5, 0.8, 1.0] {
+                for _ in 0..50 {
+                    let squares = random_board(&rules, &mut rng, fill_fraction);
+                    assert!(is_legal_position(&squares, next_mark(&squares), &rules));
+                    assert_eq!(get_winner(&squares, &lines), None);
+                    assert!(squares.contains(&Square::Empty));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_boards_fill_about_the_given_fraction() {
+        let mut rng = Rng::new(3);
+        let rules = Rules { size: 5, ..Rules::default() };
+        assert_eq!(random_board(&rules, &mut rng, 0.0), vec![Square::Empty; 25]);
+        let squares = random_board(&rules, &mut rng, 0.4);
+        assert_eq!(squares.iter().filter(|&&square| square != Square::Empty).count(), 10);
+    }
+
+    #[test]
+    fn is_won_is_none_without_lines() {
+        assert_eq!(is_won(&[Square::X; 9], &[]), None);
+    }
+}
@@ -0,0 +1,177 @@
+//! Short codes for sharing a position to play from, along with the rules it is played under, such as "0c1gs8r00m".
+//! A code is a few bytes written in base 32: the board size, the win length, the rule flags, the squares packed five
+//! to a byte, and a checksum so that a mistyped code is caught rather than loading some other position.
+
+use crate::board::{Rules, Square, WinCondition};
+use crate::notation::MAX_COLUMNS;
+use std::fmt;
+
+/// The digits of a code. Letters that are easily mistaken for digits or each other are left out.
+const ALPHABET: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+/// The number of squares packed into each byte, as a base 3 number.
+const SQUARES_PER_BYTE: usize = 5;
+
+/// The number of bytes before the squares: the size, the win length, and the rule flags.
+const HEADER_BYTES: usize = 3;
+
+/// The bits of the rule flags byte, after the two bits that hold the win condition.
+const DIAGONAL_FLAG: u8 = 1 << 2;
+const ANTI_DIAGONAL_FLAG: u8 = 1 << 3;
+const HEX_FLAG: u8 = 1 << 4;
+
+/// An error in a challenge code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChallengeError {
+    /// The code has a character that is not one of its digits.
+    InvalidCharacter(char),
+    /// The code is too short or too long for the board it describes.
+    WrongLength,
+    /// The checksum does not match, so the code was most likely mistyped.
+    Checksum,
+    /// The code decodes, but to rules or squares that cannot be played.
+    InvalidRules,
+}
+
+impl fmt::Display for ChallengeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChallengeError::InvalidCharacter(c) => write!(f, "'{}' is not a character challenge codes use", c),
+            ChallengeError::WrongLength => write!(f, "the code is the wrong length, so part of it may be missing"),
+            ChallengeError::Checksum => write!(f, "the code does not add up, so it may have been mistyped"),
+            ChallengeError::InvalidRules => write!(f, "the code does not describe a board that can be played"),
+        }
+    }
+}
+
+impl std::error::Error for ChallengeError {}
+
+/// Returns the checksum of the bytes before it.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0x5a, |sum: u8, &byte| sum.rotate_left(3) ^ byte)
+}
+
+/// Returns the number of bytes a code for a board with the given number of squares has, checksum included.
+fn code_bytes(squares: usize) -> usize {
+    HEADER_BYTES + squares.div_ceil(SQUARES_PER_BYTE) + 1
+}
+
+/// Writes the board and the rules it is played under as a challenge code.
+pub fn encode_challenge(squares: &[Square], rules: &Rules) -> String {
+    let win_condition = match rules.win_condition {
+        WinCondition::Line => 0,
+        WinCondition::Corners => 1,
+        WinCondition::Block => 2,
+    };
+    let mut flags = win_condition;
+    if rules.diagonal {
+        flags |= DIAGONAL_FLAG;
+    }
+    if rules.anti_diagonal {
+        flags |= ANTI_DIAGONAL_FLAG;
+    }
+    if rules.hex {
+        flags |= HEX_FLAG;
+    }
+    let mut bytes = vec![rules.size as u8, rules.win_length as u8, flags];
+    for chunk in squares.chunks(SQUARES_PER_BYTE) {
+        // The first square of each chunk is the lowest digit.
+        bytes.push(chunk.iter().rev().fold(0, |byte, square| {
+            byte * 3 + match square {
+                Square::Empty => 0,
+                Square::X => 1,
+                Square::O => 2,
+            }
+        }));
+    }
+    bytes.push(checksum(&bytes));
+
+    // The bytes are read five bits at a time, from the highest bit down, with zeros filling out the last digit.
+    let mut code = String::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for &byte in &bytes {
+        bits = (bits << 8) | byte as u32;
+        count += 8;
+        while count >= 5 {
+            count -= 5;
+            code.push(ALPHABET[((bits >> count) & 31) as usize] as char);
+        }
+    }
+    if count > 0 {
+        code.push(ALPHABET[((bits << (5 - count)) & 31) as usize] as char);
+    }
+    code
+}
+
+/// Reads a challenge code, returning the board and the rules it is played under. Upper and lower case are the same.
+pub fn decode_challenge(code: &str) -> Result<(Vec<Square>, Rules), ChallengeError> {
+    let mut bytes = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for c in code.trim().chars() {
+        let digit = ALPHABET
+            .iter()
+            .position(|&digit| digit as char == c.to_ascii_lowercase())
+            .ok_or(ChallengeError::InvalidCharacter(c))?;
+        bits = (bits << 5) | digit as u32;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    // The bits left over after the last byte only fill out the last digit, so they are zero in a code that was typed right.
+    if bits & ((1 << count) - 1) != 0 {
+        return Err(ChallengeError::Checksum);
+    }
+    if bytes.len() < HEADER_BYTES + 1 {
+        return Err(ChallengeError::WrongLength);
+    }
+    let (body, sum) = bytes.split_at(bytes.len() - 1);
+    if checksum(body) != sum[0] {
+        return Err(ChallengeError::Checksum);
+    }
+
+    let (size, win_length, flags) = (body[0] as usize, body[1] as usize, body[2]);
+    if size == 0 || size > MAX_COLUMNS {
+        return Err(ChallengeError::InvalidRules);
+    }
+    if bytes.len() != code_bytes(size * size) {
+        return Err(ChallengeError::WrongLength);
+    }
+    let win_condition = match flags & 3 {
+        0 => WinCondition::Line,
+        1 => WinCondition::Corners,
+        2 => WinCondition::Block,
+        _ => return Err(ChallengeError::InvalidRules),
+    };
+    let rules = Rules {
+        size,
+        win_condition,
+        win_length,
+        diagonal: flags & DIAGONAL_FLAG != 0,
+        anti_diagonal: flags & ANTI_DIAGONAL_FLAG != 0,
+        hex: flags & HEX_FLAG != 0,
+    };
+
+    let mut squares = Vec::with_capacity(size * size);
+    for &byte in &body[HEADER_BYTES..] {
+        let mut value = byte;
+        for _ in 0..SQUARES_PER_BYTE {
+            squares.push(match value % 3 {
+                0 => Square::Empty,
+                1 => Square::X,
+                _ => Square::O,
+            });
+            value /= 3;
+        }
+        if value != 0 {
+            return Err(ChallengeError::InvalidRules);
+        }
+    }
+    // The padding after the last square must be empty, so that every board has just one code.
+    if squares[size * size..].iter().any(|&square| square != Square::Empty) {
+        return Err(ChallengeError::InvalidRules);
+    }
+    squares.truncate(size * size);
+    Ok((squares, rules))
+}
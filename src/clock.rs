@@ -0,0 +1,92 @@
+//! A chess clock for blitz games, where each player has a time budget for the whole game that only runs on their turn.
+
+use crate::{config::Config, font, theme::Theme, BORDER_THICKNESS, PLAYING_AREA_OFFSET, WINDOW_SIZE};
+use sdl2::pixels::Color;
+use sdl2::render::WindowCanvas;
+use std::time::{Duration, Instant};
+use tic_tac_toe::board::Square;
+
+/// The size of each font pixel in the clocks, in screen pixels.
+const CLOCK_TEXT_SCALE: u32 = 2;
+
+/// The opacity of the clock of the player who is not moving.
+const WAITING_CLOCK_ALPHA: u8 = 128;
+
+pub struct Clock {
+    x_remaining: Duration,
+    o_remaining: Duration,
+    /// The last time the clock was charged, or None while it is stopped.
+    last_tick: Option<Instant>,
+}
+
+impl Clock {
+    /// Returns a stopped clock that gives each player the given budget.
+    pub fn new(budget: Duration) -> Self {
+        Self { x_remaining: budget, o_remaining: budget, last_tick: None }
+    }
+
+    /// Returns a stopped clock with the given time left for Red and for Blue, such as from a saved game.
+    pub fn resume(x_remaining: Duration, o_remaining: Duration) -> Self {
+        Self { x_remaining, o_remaining, last_tick: None }
+    }
+
+    /// Returns the time the player has left at the given time, counting the time since the clock last ran against the
+    /// player to move.
+    pub fn remaining_at(&self, player: Square, to_move: Square, now: Instant) -> Duration {
+        match self.last_tick {
+            Some(last_tick) if player == to_move => self.remaining(player).saturating_sub(now.saturating_duration_since(last_tick)),
+            _ => self.remaining(player),
+        }
+    }
+
+    /// Returns the time the player has left.
+    pub fn remaining(&self, player: Square) -> Duration {
+        if player == Square::X { self.x_remaining } else { self.o_remaining }
+    }
+
+    /// Returns whether the player has run out of time.
+    pub fn flagged(&self, player: Square) -> bool {
+        self.remaining(player).is_zero()
+    }
+
+    /// Charges the time since the clock last ran to the player to move, starting the clock if it was stopped.
+    pub fn run(&mut self, to_move: Square, now: Instant) {
+        if let Some(last_tick) = self.last_tick {
+            let remaining = if to_move == Square::X { &mut self.x_remaining } else { &mut self.o_remaining };
+            *remaining = remaining.saturating_sub(now - last_tick);
+        }
+        self.last_tick = Some(now);
+    }
+
+    /// Stops the clock, so that the time until it next runs is not charged to anyone.
+    pub fn stop(&mut self) {
+        self.last_tick = None;
+    }
+
+    /// Draws both clocks in the top border, Red on the left and Blue on the right, dimming the waiting player's.
+    pub fn draw(&self, canvas: &mut WindowCanvas, to_move: Square, theme: &Theme, config: &Config) {
+        let y = BORDER_THICKNESS + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * CLOCK_TEXT_SCALE) as i32) / 2;
+        for &player in [Square::X, Square::O].iter() {
+            let text = format!("{} {}", config.name(player), format_remaining(self.remaining(player)));
+            let width = font::text_width(&text, CLOCK_TEXT_SCALE) as i32;
+            let x = if player == Square::X {
+                PLAYING_AREA_OFFSET as i32
+            } else {
+                (WINDOW_SIZE - PLAYING_AREA_OFFSET) as i32 - width
+            };
+            let color = if player == Square::X { theme.x } else { theme.o };
+            let alpha = if player == to_move { 255 } else { WAITING_CLOCK_ALPHA };
+            font::draw_text(canvas, &text, x, y, CLOCK_TEXT_SCALE, Color::RGBA(color.r, color.g, color.b, alpha));
+        }
+    }
+}
+
+/// Formats the time left as minutes and seconds, with tenths once under ten seconds.
+fn format_remaining(remaining: Duration) -> String {
+    if remaining < Duration::from_secs(10) {
+        return format!("0:{:04.1}", remaining.as_secs_f64());
+    }
+    // Round up, so that the clock only shows zero once time has actually run out.
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,1092 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::glyph::Shadow;
+use crate::player_name;
+use crate::tournament::Player;
+use tic_tac_toe::challenge::{decode_challenge, encode_challenge, ChallengeError};
+use tic_tac_toe::board::{format_board, is_legal_position, next_mark, parse_board, Rules, Square, TieBreaker, WinCondition};
+use tic_tac_toe::notation::MAX_COLUMNS;
+
+/// The usage text printed when the arguments cannot be parsed.
+pub const USAGE: &str = "\
+Usage: tic-tac-toe [OPTIONS]
+
+Options:
+    --size <squares>     Play on a board this many squares across (default: 3)
+    --win-length <count> The number of marks in a row needed to win (default: the board size)
+    --win <shape>        What wins: line, corners (all four), or block (any 2x2) (default: line)
+    --tie-breaker <rule> Decide draws by who holds more of the center, or of the squares around it: center or around
+    --grow <size>        After each win, play the next game on a board one size bigger, up to this size
+    --hex                Play on a board of hexagons
+    --board <squares>    Start the first game from a board written row by row with X, O, and .
+    --challenge <code>   Start the first game from a shared challenge code, under the rules it was made with
+    --no-diagonals       Diagonal lines do not count as a win
+    --no-anti-diagonal   The bottom-left to top-right diagonal does not count as a win
+    --software           Draw without the graphics card, for drivers that draw the game wrongly
+    --accelerated        Draw with the graphics card, synced to the display
+    --theme <file>       Load colors from a theme file
+    --font <file>        Draw text with this TrueType font (needs the ttf feature)
+    --save <file>        Save the game to this file when S is pressed (default: tic-tac-toe.save)
+    --history <file>     Add every finished game to the end of this file
+    --resume <file>      Continue a game saved with S
+    --script <file>      Play the moves in this file, as indices or names like b2, before the first game goes on as usual
+    --best-of <games>    Play matches of this many games, showing the score between them
+    --place-on-release   Place marks when the mouse button is released over the square it was pressed on
+    --alternate          Take turns moving first, starting with Red
+    --random-start       Pick who moves first in each game at random
+    --sandbox            Edit the board freely: right-click clears a square and games never end
+    --occupied <action>  What clicking a taken square does: ignore, warn, or overwrite (sandbox only) (default: ignore)
+    --confirm-quit       Ask before Escape quits in the middle of a game
+    --ready              Start each game once Red has pressed Q and Blue has pressed P, to say they are ready
+    --hover-sound        Tick when the mouse moves onto another square; A toggles this
+    --tooltip            Show the name and mark of the square under the mouse next to it; F5 toggles this
+    --min-square <pixels> Never shrink squares below this size, panning boards too big to fit with the arrow keys
+    --gap <pixels>       Leave this much space between squares, drawing them as separate tiles (default: 0)
+    --glyphs             Draw marks as the letters X and O instead of filling their squares
+    --smooth             Smooth the edges of glyphs by drawing them bigger and scaling them down, which is slower
+    --turn-tint          Tint the border toward the color of the player to move
+    --guides             Draw faint guides under the marks on square boards: the winning diagonals and the center
+    --stroke <pixels>    The stroke width of glyphs (default: a sixteenth of a square)
+    --shadow <pixels>    Draw a shadow this far behind each glyph
+    --shadow-alpha <n>   The opacity of glyph shadows, from 0 to 255 (default: 96)
+    --blindfold          Marks fade away after being placed; hold Space to see them
+    --shake              Shake the board when a game is won
+    --flash              Flash the winner's color when a game is won
+    --ai                 Play against the computer, which plays Blue
+    --x-name <name>      The name shown for Red, who plays X (default: Red)
+    --o-name <name>      The name shown for Blue, who plays O (default: Blue)
+    --host <port>        Host a game over the network on this port, playing Red
+    --connect <address>  Join a game hosted at this address, like 192.168.1.5:7878, playing Blue
+    --engine <command>   Run this program to choose the computer's moves, sending it each board to answer
+    --explain            Say why the computer played each of its moves
+    --blunder <chance>   The chance, from 0.0 to 1.0, that the computer plays a random move instead of its best one
+    --think <millis>     The most time the computer spends on a move (default: 500)
+    --restart <millis>   The time between a game ending and the next one starting (default: 2000)
+    --hold-result        Keep a finished game and its result up until a key or click starts the next one
+    --result <millis>    The time the result of a game is shown over the board, even into the next game (default: 2000)
+    --show-lost          Show when the computer has a forced win; L toggles this while playing
+    --hint               Point an arrow at the best move for the player to move; G toggles this
+    --drill              Practice openings: after each first move, say whether it was one of the best and start again
+    --forks              Outline the squares that would make a fork for the player to move; F toggles this
+    --eval               Dot each empty square green, yellow or red if playing there wins, draws or loses; E toggles this
+    --max-moves <count>  Declare a draw once this many moves have been played, here and in tournaments
+    --clock <seconds>    Give each player this much time for the whole game; running out loses
+    --dump-lines         Print the winning lines for the board as square indices and exit, without a window
+    --research <games>   Play this many AI games from random positions without a window and print who won
+    --tournament <games> Play this many games between the --red and --blue players without a window
+    --red <player>       The tournament player for Red: perfect or random (default: perfect)
+    --blue <player>      The tournament player for Blue: perfect or random (default: perfect)
+    --report <file>      The CSV file the tournament is written to (default: tournament.csv)
+    --seed <number>      Seed anything random, so that it can be reproduced
+    --config <file>      Load settings from a file written by --dump-config; options after it override them
+    --dump-config <file> Write the settings to this file as TOML and exit, to load again with --config";
+
+/// The file games are saved to when no other is given.
+const DEFAULT_SAVE_PATH: &str = "tic-tac-toe.save";
+
+/// The time the computer may spend on a move when no other is given, in milliseconds.
+const DEFAULT_THINK_MILLIS: u64 = 500;
+
+/// The time between a game ending and the next one starting when no other is given, in milliseconds.
+const DEFAULT_RESTART_MILLIS: u64 = 2000;
+
+/// The time the result of a game is shown for when no other is given, in milliseconds.
+const DEFAULT_RESULT_MILLIS: u64 = 2000;
+
+/// The opacity of glyph shadows when no other is given.
+const DEFAULT_SHADOW_ALPHA: u8 = 96;
+
+/// The file tournaments are reported to when no other is given.
+const DEFAULT_REPORT_PATH: &str = "tournament.csv";
+
+/// Who moves first in each game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartOrder {
+    /// Red always moves first.
+    RedFirst,
+    /// The players take turns moving first.
+    Alternate,
+    /// A random player moves first.
+    Random,
+}
+
+/// How the window is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Renderer {
+    /// Whatever SDL picks.
+    Default,
+    /// Drawn by the processor, without the graphics card.
+    Software,
+    /// Drawn by the graphics card, synced to the display.
+    Accelerated,
+}
+
+/// What clicking a square that already has a mark does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OccupiedClick {
+    /// Nothing happens.
+    Ignore,
+    /// A message says the square is taken.
+    Warn,
+    /// The mark is replaced with the mark of the player to move, which is only allowed in the sandbox.
+    Overwrite,
+}
+
+/// How to reach the other player in a game over the network.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Network {
+    /// Wait for the other player to connect on the given port.
+    Host(u16),
+    /// Connect to the other player at the given address.
+    Connect(String),
+}
+
+impl Network {
+    /// Returns the option the network game was chosen with.
+    fn flag(&self) -> &'static str {
+        match self {
+            Network::Host(_) => "--host",
+            Network::Connect(_) => "--connect",
+        }
+    }
+}
+
+/// Settings chosen on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub rules: Rules,
+    /// How drawn games are decided, or None to leave them drawn.
+    pub tie_breaker: Option<TieBreaker>,
+    /// The board the first game starts from, if not empty.
+    pub board: Option<Vec<Square>>,
+    pub theme: Option<PathBuf>,
+    /// The TrueType font text is drawn with, in place of the built-in one.
+    pub font: Option<PathBuf>,
+    /// How the window is drawn.
+    pub renderer: Renderer,
+    /// The file the game is saved to.
+    pub save: PathBuf,
+    /// The file every finished game is added to, if any.
+    pub history: Option<PathBuf>,
+    /// The saved game to continue, if any.
+    pub resume: Option<PathBuf>,
+    /// The file of moves played out at the start of the first game, if any.
+    pub script: Option<PathBuf>,
+    /// The number of games in a match, if playing matches.
+    pub best_of: Option<u32>,
+    /// Whether marks are placed when the mouse button is released rather than pressed.
+    pub place_on_release: bool,
+    /// Who moves first in each game.
+    pub start_order: StartOrder,
+    /// Whether the board can be edited freely, without games ending.
+    pub sandbox: bool,
+    /// What clicking a square that already has a mark does.
+    pub occupied: OccupiedClick,
+    /// Whether a tick plays when the mouse moves onto another square.
+    pub hover_sound: bool,
+    /// Whether the square under the mouse is described next to it.
+    pub tooltip: bool,
+    /// The smallest squares are drawn at, in pixels, if boards too big to fit at that size are panned rather than shrunk.
+    pub min_square: Option<u32>,
+    /// The space left between squares, in pixels. Clicks in the space do not play anywhere.
+    pub gap: u32,
+    /// Whether Escape asks before quitting in the middle of a game.
+    pub confirm_quit: bool,
+    /// Whether each game waits for both players to say they are ready.
+    pub ready: bool,
+    /// Whether marks are drawn as the letters X and O.
+    pub glyphs: bool,
+    /// Whether glyphs are drawn bigger and scaled down, for smoother edges.
+    pub smooth: bool,
+    /// Whether the border is tinted toward the color of the player to move.
+    pub turn_tint: bool,
+    /// Whether to draw faint guides on the board under the marks.
+    pub guides: bool,
+    /// The stroke width of glyphs, or None to scale it with the squares.
+    pub stroke: Option<u32>,
+    /// The shadow drawn behind glyphs, if any.
+    pub shadow: Option<Shadow>,
+    /// Whether marks fade away after being placed.
+    pub blindfold: bool,
+    /// Whether the board shakes when a game is won.
+    pub shake: bool,
+    /// Whether the window flashes the winner's color when a game is won.
+    pub flash: bool,
+    /// Whether Blue is played by the computer.
+    pub ai: bool,
+    /// The names shown for Red and Blue, in that order.
+    pub names: [String; 2],
+    /// Whether to say why the computer played each of its moves.
+    pub explain: bool,
+    /// The command for a program that chooses the computer's moves in place of its own search.
+    pub engine: Option<String>,
+    /// The chance that the computer plays a random move instead of its best one, from 0.0 to 1.0.
+    pub blunder: f64,
+    /// The most time the computer spends on a move.
+    pub think: Duration,
+    /// The time between a game ending and the next one starting.
+    pub restart: Duration,
+    /// Whether a finished game stays up, without a countdown, until a key or click starts the next one.
+    pub hold_result: bool,
+    /// The time the result of a game is shown for.
+    pub result: Duration,
+    /// The other player, if playing over the network.
+    pub network: Option<Network>,
+    /// Whether to show when the computer has a forced win.
+    pub show_lost: bool,
+    /// Whether to point an arrow at the best move for the player to move.
+    pub show_hint: bool,
+    /// Whether each game ends after its first move, to practice openings.
+    pub drill: bool,
+    /// The size the board stops growing at, if it grows by one after each win.
+    pub grow: Option<usize>,
+    /// Whether to outline the squares that would make a fork for the player to move.
+    pub show_forks: bool,
+    /// Whether to mark each empty square with the outcome of playing there.
+    pub show_eval: bool,
+    /// The number of moves after which a game is drawn, if games are capped.
+    pub max_moves: Option<u32>,
+    /// The time each player has for a whole game, if games are timed.
+    pub clock: Option<Duration>,
+    /// Whether to print the winning lines for the board and exit instead of opening a window.
+    pub dump_lines: bool,
+    /// The number of headless games to play for research instead of opening a window.
+    pub research: Option<u32>,
+    /// The number of headless games to play between the tournament players instead of opening a window.
+    pub tournament: Option<u32>,
+    /// The tournament player for Red.
+    pub red: Player,
+    /// The tournament player for Blue.
+    pub blue: Player,
+    /// The file the tournament is reported to.
+    pub report: PathBuf,
+    /// The seed for anything random, or None to pick one from the clock.
+    pub seed: Option<u64>,
+    /// The file to write the settings to instead of playing, if any. It is not one of the settings written.
+    pub dump_config: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rules: Rules::default(),
+            tie_breaker: None,
+            board: None,
+            theme: None,
+            font: None,
+            renderer: Renderer::Default,
+            save: PathBuf::from(DEFAULT_SAVE_PATH),
+            history: None,
+            resume: None,
+            script: None,
+            best_of: None,
+            place_on_release: false,
+            start_order: StartOrder::RedFirst,
+            sandbox: false,
+            occupied: OccupiedClick::Ignore,
+            hover_sound: false,
+            tooltip: false,
+            min_square: None,
+            gap: 0,
+            confirm_quit: false,
+            ready: false,
+            glyphs: false,
+            smooth: false,
+            turn_tint: false,
+            guides: false,
+            stroke: None,
+            shadow: None,
+            blindfold: false,
+            shake: false,
+            flash: false,
+            ai: false,
+            names: [player_name(Square::X).to_string(), player_name(Square::O).to_string()],
+            explain: false,
+            engine: None,
+            blunder: 0.0,
+            think: Duration::from_millis(DEFAULT_THINK_MILLIS),
+            restart: Duration::from_millis(DEFAULT_RESTART_MILLIS),
+            hold_result: false,
+            result: Duration::from_millis(DEFAULT_RESULT_MILLIS),
+            network: None,
+            show_lost: false,
+            show_hint: false,
+            drill: false,
+            grow: None,
+            show_forks: false,
+            show_eval: false,
+            max_moves: None,
+            clock: None,
+            dump_lines: false,
+            research: None,
+            tournament: None,
+            red: Player::Perfect,
+            blue: Player::Perfect,
+            report: PathBuf::from(DEFAULT_REPORT_PATH),
+            seed: None,
+            dump_config: None,
+        }
+    }
+}
+
+/// An error in the command line arguments.
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    InvalidBoard { board: String, squares: usize },
+    InvalidChallenge { code: String, error: ChallengeError },
+    IllegalBoard(String),
+    OutOfRange { flag: &'static str, value: usize, min: usize, max: usize },
+    WinLengthTooLong { win_length: usize, size: usize },
+    ConflictingOptions(&'static str, &'static str),
+    RequiresOption(&'static str, &'static str),
+    UnreadableConfig { path: PathBuf, error: String },
+    InvalidConfigLine { path: PathBuf, line: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::UnknownFlag(flag) => write!(f, "unknown option '{}'", flag),
+            ConfigError::MissingValue(flag) => write!(f, "option '{}' needs a value", flag),
+            ConfigError::InvalidValue { flag, value } => write!(f, "invalid value '{}' for option '{}'", value, flag),
+            ConfigError::InvalidBoard { board, squares } => write!(
+                f, "invalid board '{}': expected {} squares of X, O, or .", board, squares,
+            ),
+            ConfigError::InvalidChallenge { code, error } => write!(f, "invalid challenge code '{}': {}", code, error),
+            ConfigError::IllegalBoard(board) => write!(
+                f,
+                "illegal board '{}': X moves first, so X must have as many marks as O or one more, \
+                 and only the player who moved last can have a line",
+                board,
+            ),
+            ConfigError::OutOfRange { flag, value, min, max } => write!(
+                f, "invalid value {} for option '{}': it must be between {} and {}", value, flag, min, max,
+            ),
+            ConfigError::WinLengthTooLong { win_length, size } => write!(
+                f,
+                "option '--win-length' is {}, but the board is only {} squares across: \
+                 lower '--win-length' to {} or less, or raise '--size' to {} or more",
+                win_length, size, size, win_length,
+            ),
+            ConfigError::ConflictingOptions(first, second) => {
+                write!(f, "options '{}' and '{}' cannot be used together", first, second)
+            },
+            ConfigError::RequiresOption(option, required) => {
+                write!(f, "option '{}' can only be used with '{}'", option, required)
+            },
+            ConfigError::UnreadableConfig { path, error } => {
+                write!(f, "cannot read config file '{}': {}", path.display(), error)
+            },
+            ConfigError::InvalidConfigLine { path, line } => write!(
+                f, "line {} of config file '{}' is not a setting like 'size = 4'", line, path.display(),
+            ),
+        }
+    }
+}
+
+/// Parses the value of an option that names a win condition.
+fn parse_win_condition(flag: &str, value: Option<String>) -> Result<WinCondition, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    match value.as_str() {
+        "line" => Ok(WinCondition::Line),
+        "corners" => Ok(WinCondition::Corners),
+        "block" => Ok(WinCondition::Block),
+        _ => Err(ConfigError::InvalidValue { flag: flag.to_string(), value }),
+    }
+}
+
+/// Parses the value of an option that names a tie-breaker.
+fn parse_tie_breaker(flag: &str, value: Option<String>) -> Result<TieBreaker, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    match value.as_str() {
+        "center" => Ok(TieBreaker::Center),
+        "around" => Ok(TieBreaker::AroundCenter),
+        _ => Err(ConfigError::InvalidValue { flag: flag.to_string(), value }),
+    }
+}
+
+/// Parses the value of an option that says what clicking a taken square does.
+fn parse_occupied_click(flag: &str, value: Option<String>) -> Result<OccupiedClick, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    match value.as_str() {
+        "ignore" => Ok(OccupiedClick::Ignore),
+        "warn" => Ok(OccupiedClick::Warn),
+        "overwrite" => Ok(OccupiedClick::Overwrite),
+        _ => Err(ConfigError::InvalidValue { flag: flag.to_string(), value }),
+    }
+}
+
+/// Parses the value of an option that names a tournament player.
+fn parse_player(flag: &str, value: Option<String>) -> Result<Player, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    Player::parse(&value).ok_or_else(|| ConfigError::InvalidValue { flag: flag.to_string(), value })
+}
+
+/// Parses the value of an option that counts something, which must be at least one.
+fn parse_count(flag: &str, value: Option<String>) -> Result<u32, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(ConfigError::InvalidValue { flag: flag.to_string(), value }),
+    }
+}
+
+/// Parses a chance from 0.0 to 1.0.
+fn parse_chance(flag: &str, value: Option<String>) -> Result<f64, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError::MissingValue(flag.to_string()))?;
+    match value.parse::<f64>() {
+        Ok(chance) if (0.0..=1.0).contains(&chance) => Ok(chance),
+        _ => Err(ConfigError::InvalidValue { flag: flag.to_string(), value }),
+    }
+}
+
+/// A setting written to a config file, under the name of its option without the dashes.
+enum Value {
+    /// An option that takes no value, written as true.
+    Flag,
+    Number(String),
+    Text(String),
+}
+
+/// Reads a config file written by `Config::to_toml` into the options it stands for. A flag set to false is left out,
+/// so that it can be turned off by hand.
+fn read_config_file(path: &Path) -> Result<Vec<String>, ConfigError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| ConfigError::UnreadableConfig { path: path.to_path_buf(), error: e.to_string() })?;
+    let mut args = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || ConfigError::InvalidConfigLine { path: path.to_path_buf(), line: number + 1 };
+        let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+        let (key, value) = (key.trim(), value.trim());
+        // A file that loads other files could load itself forever.
+        if key.is_empty() || key == "config" || key == "dump-config" {
+            return Err(invalid());
+        }
+        match value {
+            "true" => args.push(format!("--{}", key)),
+            "false" => {},
+            _ if value.starts_with('"') => {
+                let value = unquote(value).ok_or_else(invalid)?;
+                args.extend([format!("--{}", key), value]);
+            },
+            _ => args.extend([format!("--{}", key), value.to_string()]),
+        }
+    }
+    Ok(args)
+}
+
+/// Writes text as a TOML string, in quotes.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Reads a TOML string written by `quote`, or returns None if it is not one.
+fn unquote(quoted: &str) -> Option<String> {
+    let mut chars = quoted.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                't' => '\t',
+                _ => return None,
+            }),
+            '"' => return None,
+            _ => text.push(c),
+        }
+    }
+    Some(text)
+}
+
+/// Checks that the settings fit together, so that they can be reported before a window opens.
+pub fn validate_config(config: &Config) -> Result<(), ConfigError> {
+    let rules = &config.rules;
+    if rules.size < 1 || rules.size > MAX_COLUMNS {
+        return Err(ConfigError::OutOfRange { flag: "--size", value: rules.size, min: 1, max: MAX_COLUMNS });
+    }
+    if rules.win_condition == WinCondition::Block && rules.size < 2 {
+        return Err(ConfigError::OutOfRange { flag: "--size", value: rules.size, min: 2, max: MAX_COLUMNS });
+    }
+    if rules.win_length < 1 {
+        return Err(ConfigError::OutOfRange { flag: "--win-length", value: rules.win_length, min: 1, max: rules.size });
+    }
+    if rules.win_length > rules.size {
+        return Err(ConfigError::WinLengthTooLong { win_length: rules.win_length, size: rules.size });
+    }
+    // Outside the sandbox, overwriting would let a player take back the other player's move.
+    if config.occupied == OccupiedClick::Overwrite && !config.sandbox {
+        return Err(ConfigError::RequiresOption("--occupied overwrite", "--sandbox"));
+    }
+    if config.smooth && !config.glyphs {
+        return Err(ConfigError::RequiresOption("--smooth", "--glyphs"));
+    }
+    if config.blunder > 0.0 && !config.ai {
+        return Err(ConfigError::RequiresOption("--blunder", "--ai"));
+    }
+    if config.engine.is_some() && !config.ai {
+        return Err(ConfigError::RequiresOption("--engine", "--ai"));
+    }
+    if config.explain && !config.ai {
+        return Err(ConfigError::RequiresOption("--explain", "--ai"));
+    }
+    if let (true, Some(network)) = (config.ai, &config.network) {
+        return Err(ConfigError::ConflictingOptions("--ai", network.flag()));
+    }
+    if let Some(max_size) = config.grow {
+        if max_size < rules.size || max_size > MAX_COLUMNS {
+            return Err(ConfigError::OutOfRange { flag: "--grow", value: max_size, min: rules.size, max: MAX_COLUMNS });
+        }
+        if config.drill {
+            return Err(ConfigError::ConflictingOptions("--grow", "--drill"));
+        }
+    }
+    // The drill clears the board after the first move, with the rest of the script still to play.
+    if let (Some(_), true) = (&config.script, config.drill) {
+        return Err(ConfigError::ConflictingOptions("--script", "--drill"));
+    }
+    // Only players sharing this computer need to say they are ready.
+    if config.ready && config.ai {
+        return Err(ConfigError::ConflictingOptions("--ready", "--ai"));
+    }
+    if let (true, Some(network)) = (config.ready, &config.network) {
+        return Err(ConfigError::ConflictingOptions("--ready", network.flag()));
+    }
+    // The other player would not see the scripted moves.
+    if let (Some(_), Some(network)) = (&config.script, &config.network) {
+        return Err(ConfigError::ConflictingOptions("--script", network.flag()));
+    }
+    // Each side would start the next game whenever its own player is ready, so the games would drift apart.
+    if let (true, Some(network)) = (config.hold_result, &config.network) {
+        return Err(ConfigError::ConflictingOptions("--hold-result", network.flag()));
+    }
+    if let (true, Some(network)) = (config.drill, &config.network) {
+        return Err(ConfigError::ConflictingOptions("--drill", network.flag()));
+    }
+    // Each side would pick a different random player to start.
+    if let (StartOrder::Random, Some(network)) = (config.start_order, &config.network) {
+        return Err(ConfigError::ConflictingOptions("--random-start", network.flag()));
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Builds a config from the command line arguments, excluding the program name.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        let mut args = args.into_iter().collect::<Vec<_>>().into_iter();
+        let mut board = None;
+        let mut win_length = None;
+        let mut shadow_alpha = None;
+        let mut challenge = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--no-diagonals" => {
+                    config.rules.diagonal = false;
+                    config.rules.anti_diagonal = false;
+                },
+                "--no-anti-diagonal" => config.rules.anti_diagonal = false,
+                "--hex" => config.rules.hex = true,
+                "--size" => config.rules.size = parse_count(&arg, args.next())? as usize,
+                "--grow" => config.grow = Some(parse_count(&arg, args.next())? as usize),
+                "--win" => config.rules.win_condition = parse_win_condition(&arg, args.next())?,
+                "--tie-breaker" => config.tie_breaker = Some(parse_tie_breaker(&arg, args.next())?),
+                "--win-length" => win_length = Some(parse_count(&arg, args.next())? as usize),
+                "--software" => config.renderer = Renderer::Software,
+                "--accelerated" => config.renderer = Renderer::Accelerated,
+                "--theme" => config.theme = Some(PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--font" => config.font = Some(PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--save" => config.save = PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?),
+                "--history" => config.history = Some(PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--resume" => config.resume = Some(PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--script" => config.script = Some(PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--best-of" => config.best_of = Some(parse_count(&arg, args.next())?),
+                "--place-on-release" => config.place_on_release = true,
+                "--alternate" => config.start_order = StartOrder::Alternate,
+                "--random-start" => config.start_order = StartOrder::Random,
+                "--sandbox" => config.sandbox = true,
+                "--occupied" => config.occupied = parse_occupied_click(&arg, args.next())?,
+                "--hover-sound" => config.hover_sound = true,
+                "--tooltip" => config.tooltip = true,
+                "--min-square" => config.min_square = Some(parse_count(&arg, args.next())?),
+                "--gap" => {
+                    let value = args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+                    config.gap = value.parse().map_err(|_| ConfigError::InvalidValue { flag: arg, value })?;
+                },
+                "--confirm-quit" => config.confirm_quit = true,
+                "--ready" => config.ready = true,
+                "--glyphs" => config.glyphs = true,
+                "--smooth" => config.smooth = true,
+                "--turn-tint" => config.turn_tint = true,
+                "--guides" => config.guides = true,
+                "--stroke" => config.stroke = Some(parse_count(&arg, args.next())?),
+                "--shadow" => config.shadow = Some(Shadow { offset: parse_count(&arg, args.next())?, alpha: DEFAULT_SHADOW_ALPHA }),
+                "--shadow-alpha" => {
+                    let value = args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+                    shadow_alpha = Some(value.parse().map_err(|_| ConfigError::InvalidValue { flag: arg, value })?);
+                },
+                "--blindfold" => config.blindfold = true,
+                "--shake" => config.shake = true,
+                "--flash" => config.flash = true,
+                "--ai" => config.ai = true,
+                "--host" => {
+                    let value = args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+                    let port = value.parse().map_err(|_| ConfigError::InvalidValue { flag: arg, value })?;
+                    config.network = Some(Network::Host(port));
+                },
+                "--connect" => config.network = Some(Network::Connect(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--x-name" => config.names[0] = args.next().ok_or(ConfigError::MissingValue(arg))?,
+                "--o-name" => config.names[1] = args.next().ok_or(ConfigError::MissingValue(arg))?,
+                "--explain" => config.explain = true,
+                "--engine" => config.engine = Some(args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?),
+                "--blunder" => config.blunder = parse_chance(&arg, args.next())?,
+                "--think" => config.think = Duration::from_millis(parse_count(&arg, args.next())?.into()),
+                "--restart" => config.restart = Duration::from_millis(parse_count(&arg, args.next())?.into()),
+                "--hold-result" => config.hold_result = true,
+                "--result" => config.result = Duration::from_millis(parse_count(&arg, args.next())?.into()),
+                "--show-lost" => config.show_lost = true,
+                "--hint" => config.show_hint = true,
+                "--drill" => config.drill = true,
+                "--forks" => config.show_forks = true,
+                "--eval" => config.show_eval = true,
+                "--max-moves" => config.max_moves = Some(parse_count(&arg, args.next())?),
+                "--clock" => config.clock = Some(Duration::from_secs(parse_count(&arg, args.next())?.into())),
+                "--dump-lines" => config.dump_lines = true,
+                "--research" => config.research = Some(parse_count(&arg, args.next())?),
+                "--tournament" => config.tournament = Some(parse_count(&arg, args.next())?),
+                "--red" => config.red = parse_player(&arg, args.next())?,
+                "--blue" => config.blue = parse_player(&arg, args.next())?,
+                "--report" => config.report = PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?),
+                "--seed" => {
+                    let value = args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+                    config.seed = Some(value.parse().map_err(|_| ConfigError::InvalidValue { flag: arg, value })?);
+                },
+                "--board" => board = Some(args.next().ok_or(ConfigError::MissingValue(arg))?),
+                "--challenge" => challenge = Some(args.next().ok_or(ConfigError::MissingValue(arg))?),
+                "--dump-config" => config.dump_config = Some(PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?)),
+                "--config" => {
+                    let path = PathBuf::from(args.next().ok_or(ConfigError::MissingValue(arg))?);
+                    // The settings take the place of the option, so that options after it override them.
+                    let loaded = read_config_file(&path)?;
+                    let rest: Vec<String> = args.by_ref().collect();
+                    args = loaded.into_iter().chain(rest).collect::<Vec<_>>().into_iter();
+                },
+                _ => return Err(ConfigError::UnknownFlag(arg)),
+            }
+        }
+        // Games are won by filling a whole row unless a shorter line is asked for.
+        config.rules.win_length = win_length.unwrap_or(config.rules.size);
+        // A challenge brings its own rules, which replace any given as options.
+        let challenge = match challenge {
+            Some(_) if board.is_some() => return Err(ConfigError::ConflictingOptions("--challenge", "--board")),
+            Some(code) => {
+                let (squares, rules) = decode_challenge(&code).map_err(|error| ConfigError::InvalidChallenge { code: code.clone(), error })?;
+                config.rules = rules;
+                Some((code, squares))
+            },
+            None => None,
+        };
+        if let (Some(shadow), Some(alpha)) = (&mut config.shadow, shadow_alpha) {
+            shadow.alpha = alpha;
+        }
+        validate_config(&config)?;
+        if let Some((code, squares)) = challenge {
+            if !is_legal_position(&squares, next_mark(&squares), &config.rules) {
+                return Err(ConfigError::InvalidChallenge { code, error: ChallengeError::InvalidRules });
+            }
+            config.board = Some(squares);
+        }
+
+        // The board is checked last so that the win rules apply wherever they appear.
+        if let Some(text) = board {
+            let squares = parse_board(&text, config.rules.size)
+                .ok_or_else(|| ConfigError::InvalidBoard { board: text.clone(), squares: config.rules.squares() })?;
+            if !is_legal_position(&squares, next_mark(&squares), &config.rules) {
+                return Err(ConfigError::IllegalBoard(text));
+            }
+            config.board = Some(squares);
+        }
+        Ok(config)
+    }
+
+    /// Returns the settings as a TOML file that `--config` loads back into the same config.
+    pub fn to_toml(&self) -> String {
+        let mut toml = String::from("# tic-tac-toe settings, written by --dump-config\n");
+        for (key, value) in self.settings() {
+            match value {
+                Value::Flag => toml.push_str(&format!("{} = true\n", key)),
+                Value::Number(number) => toml.push_str(&format!("{} = {}\n", key, number)),
+                Value::Text(text) => toml.push_str(&format!("{} = {}\n", key, quote(&text))),
+            }
+        }
+        toml
+    }
+
+    /// Returns the options that build this config, by name without the dashes. Options left at their defaults are
+    /// left out unless they take a value.
+    fn settings(&self) -> Vec<(&'static str, Value)> {
+        let number = |number: &dyn fmt::Display| Value::Number(number.to_string());
+        let path = |path: &Path| Value::Text(path.to_string_lossy().into_owned());
+        let mut settings = Vec::new();
+        let rules = &self.rules;
+        if !rules.diagonal && rules.anti_diagonal {
+            // Only a challenge can turn off the main diagonal alone, and it brings a board with it.
+            let empty = vec![Square::Empty; rules.squares()];
+            let board = self.board.as_deref().unwrap_or(&empty);
+            settings.push(("challenge", Value::Text(encode_challenge(board, rules))));
+        } else {
+            settings.push(("size", number(&rules.size)));
+            if rules.win_length != rules.size {
+                settings.push(("win-length", number(&rules.win_length)));
+            }
+            let win = match rules.win_condition {
+                WinCondition::Line => "line",
+                WinCondition::Corners => "corners",
+                WinCondition::Block => "block",
+            };
+            settings.push(("win", Value::Text(win.to_string())));
+            if rules.hex {
+                settings.push(("hex", Value::Flag));
+            }
+            if !rules.diagonal {
+                settings.push(("no-diagonals", Value::Flag));
+            } else if !rules.anti_diagonal {
+                settings.push(("no-anti-diagonal", Value::Flag));
+            }
+            if let Some(board) = &self.board {
+                settings.push(("board", Value::Text(format_board(board))));
+            }
+        }
+        if let Some(tie_breaker) = self.tie_breaker {
+            let rule = match tie_breaker {
+                TieBreaker::Center => "center",
+                TieBreaker::AroundCenter => "around",
+            };
+            settings.push(("tie-breaker", Value::Text(rule.to_string())));
+        }
+        if let Some(max_size) = self.grow {
+            settings.push(("grow", number(&max_size)));
+        }
+        match self.renderer {
+            Renderer::Default => {},
+            Renderer::Software => settings.push(("software", Value::Flag)),
+            Renderer::Accelerated => settings.push(("accelerated", Value::Flag)),
+        }
+        if let Some(theme) = &self.theme {
+            settings.push(("theme", path(theme)));
+        }
+        if let Some(font) = &self.font {
+            settings.push(("font", path(font)));
+        }
+        settings.push(("save", path(&self.save)));
+        if let Some(history) = &self.history {
+            settings.push(("history", path(history)));
+        }
+        if let Some(resume) = &self.resume {
+            settings.push(("resume", path(resume)));
+        }
+        if let Some(script) = &self.script {
+            settings.push(("script", path(script)));
+        }
+        if let Some(games) = self.best_of {
+            settings.push(("best-of", number(&games)));
+        }
+        match self.start_order {
+            StartOrder::RedFirst => {},
+            StartOrder::Alternate => settings.push(("alternate", Value::Flag)),
+            StartOrder::Random => settings.push(("random-start", Value::Flag)),
+        }
+        let occupied = match self.occupied {
+            OccupiedClick::Ignore => "ignore",
+            OccupiedClick::Warn => "warn",
+            OccupiedClick::Overwrite => "overwrite",
+        };
+        settings.push(("occupied", Value::Text(occupied.to_string())));
+        if let Some(pixels) = self.min_square {
+            settings.push(("min-square", number(&pixels)));
+        }
+        settings.push(("gap", number(&self.gap)));
+        if let Some(pixels) = self.stroke {
+            settings.push(("stroke", number(&pixels)));
+        }
+        if let Some(shadow) = self.shadow {
+            settings.push(("shadow", number(&shadow.offset)));
+            settings.push(("shadow-alpha", number(&shadow.alpha)));
+        }
+        settings.push(("x-name", Value::Text(self.names[0].clone())));
+        settings.push(("o-name", Value::Text(self.names[1].clone())));
+        match &self.network {
+            Some(Network::Host(port)) => settings.push(("host", number(port))),
+            Some(Network::Connect(address)) => settings.push(("connect", Value::Text(address.clone()))),
+            None => {},
+        }
+        if let Some(command) = &self.engine {
+            settings.push(("engine", Value::Text(command.clone())));
+        }
+        if self.blunder > 0.0 {
+            // Debug formatting keeps the decimal point, so that the value reads back as the same float.
+            settings.push(("blunder", Value::Number(format!("{:?}", self.blunder))));
+        }
+        settings.push(("think", number(&self.think.as_millis())));
+        settings.push(("restart", number(&self.restart.as_millis())));
+        settings.push(("result", number(&self.result.as_millis())));
+        if let Some(moves) = self.max_moves {
+            settings.push(("max-moves", number(&moves)));
+        }
+        if let Some(clock) = self.clock {
+            settings.push(("clock", number(&clock.as_secs())));
+        }
+        if let Some(games) = self.research {
+            settings.push(("research", number(&games)));
+        }
+        if let Some(games) = self.tournament {
+            settings.push(("tournament", number(&games)));
+        }
+        let player = |player: Player| Value::Text(format!("{:?}", player).to_lowercase());
+        settings.push(("red", player(self.red)));
+        settings.push(("blue", player(self.blue)));
+        settings.push(("report", path(&self.report)));
+        // A seed taken from the clock is written too, so that a loaded config plays the same random games.
+        settings.push(("seed", number(&self.seed())));
+        let flags = [
+            ("place-on-release", self.place_on_release),
+            ("sandbox", self.sandbox),
+            ("hover-sound", self.hover_sound),
+            ("tooltip", self.tooltip),
+            ("confirm-quit", self.confirm_quit),
+            ("ready", self.ready),
+            ("glyphs", self.glyphs),
+            ("smooth", self.smooth),
+            ("turn-tint", self.turn_tint),
+            ("guides", self.guides),
+            ("blindfold", self.blindfold),
+            ("shake", self.shake),
+            ("flash", self.flash),
+            ("hold-result", self.hold_result),
+            ("ai", self.ai),
+            ("explain", self.explain),
+            ("show-lost", self.show_lost),
+            ("hint", self.show_hint),
+            ("drill", self.drill),
+            ("forks", self.show_forks),
+            ("eval", self.show_eval),
+            ("dump-lines", self.dump_lines),
+        ];
+        settings.extend(flags.iter().filter(|(_, on)| *on).map(|&(key, _)| (key, Value::Flag)));
+        settings
+    }
+
+    /// Returns the name shown for the player, which is their color unless another was given.
+    pub fn name(&self, player: Square) -> &str {
+        &self.names[if player == Square::X { 0 } else { 1 }]
+    }
+
+    /// Returns the configured seed, or one taken from the clock if none was given.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the error the config is rejected with.
+    fn rejected(config: Config) -> ConfigError {
+        validate_config(&config).unwrap_err()
+    }
+
+    /// Returns a config that plays over the network as the host.
+    fn hosting() -> Config {
+        Config { network: Some(Network::Host(4000)), ..Config::default() }
+    }
+
+    #[test]
+    fn the_default_config_is_valid() {
+        assert_eq!(validate_config(&Config::default()), Ok(()));
+    }
+
+    #[test]
+    fn board_sizes_must_fit() {
+        let rules = Rules { size: 0, win_length: 0, ..Rules::default() };
+        assert_eq!(
+            rejected(Config { rules, ..Config::default() }),
+            ConfigError::OutOfRange { flag: "--size", value: 0, min: 1, max: MAX_COLUMNS },
+        );
+        let rules = Rules { size: MAX_COLUMNS + 1, ..Rules::default() };
+        assert_eq!(
+            rejected(Config { rules, ..Config::default() }),
+            ConfigError::OutOfRange { flag: "--size", value: MAX_COLUMNS + 1, min: 1, max: MAX_COLUMNS },
+        );
+        let rules = Rules { size: 1, win_length: 1, win_condition: WinCondition::Block, ..Rules::default() };
+        assert_eq!(
+            rejected(Config { rules, ..Config::default() }),
+            ConfigError::OutOfRange { flag: "--size", value: 1, min: 2, max: MAX_COLUMNS },
+        );
+    }
+
+    #[test]
+    fn win_lengths_must_fit_the_board() {
+        let rules = Rules { win_length: 0, ..Rules::default() };
+        assert_eq!(
+            rejected(Config { rules, ..Config::default() }),
+            ConfigError::OutOfRange { flag: "--win-length", value: 0, min: 1, max: 3 },
+        );
+        let rules = Rules { win_length: 4, ..Rules::default() };
+        assert_eq!(rejected(Config { rules, ..Config::default() }), ConfigError::WinLengthTooLong { win_length: 4, size: 3 });
+    }
+
+    #[test]
+    fn options_that_need_another_are_rejected_without_it() {
+        assert_eq!(
+            rejected(Config { occupied: OccupiedClick::Overwrite, ..Config::default() }),
+            ConfigError::RequiresOption("--occupied overwrite", "--sandbox"),
+        );
+        assert_eq!(rejected(Config { smooth: true, ..Config::default() }), ConfigError::RequiresOption("--smooth", "--glyphs"));
+        assert_eq!(rejected(Config { blunder: 0.5, ..Config::default() }), ConfigError::RequiresOption("--blunder", "--ai"));
+        assert_eq!(
+            rejected(Config { engine: Some("engine".to_string()), ..Config::default() }),
+            ConfigError::RequiresOption("--engine", "--ai"),
+        );
+        assert_eq!(rejected(Config { explain: true, ..Config::default() }), ConfigError::RequiresOption("--explain", "--ai"));
+    }
+
+    #[test]
+    fn grow_must_not_shrink_the_board() {
+        let rules = Rules { size: 4, ..Rules::default() };
+        assert_eq!(
+            rejected(Config { rules, grow: Some(3), ..Config::default() }),
+            ConfigError::OutOfRange { flag: "--grow", value: 3, min: 4, max: MAX_COLUMNS },
+        );
+        assert_eq!(
+            rejected(Config { grow: Some(MAX_COLUMNS + 1), ..Config::default() }),
+            ConfigError::OutOfRange { flag: "--grow", value: MAX_COLUMNS + 1, min: 3, max: MAX_COLUMNS },
+        );
+    }
+
+    #[test]
+    fn conflicting_options_are_rejected() {
+        let script = Some(PathBuf::from("opening.txt"));
+        assert_eq!(rejected(Config { ai: true, ..hosting() }), ConfigError::ConflictingOptions("--ai", "--host"));
+        assert_eq!(
+            rejected(Config { ai: true, network: Some(Network::Connect("localhost:4000".to_string())), ..Config::default() }),
+            ConfigError::ConflictingOptions("--ai", "--connect"),
+        );
+        assert_eq!(rejected(Config { grow: Some(5), drill: true, ..Config::default() }), ConfigError::ConflictingOptions("--grow", "--drill"));
+        assert_eq!(
+            rejected(Config { script: script.clone(), drill: true, ..Config::default() }),
+            ConfigError::ConflictingOptions("--script", "--drill"),
+        );
+        assert_eq!(rejected(Config { ready: true, ai: true, ..Config::default() }), ConfigError::ConflictingOptions("--ready", "--ai"));
+        assert_eq!(rejected(Config { ready: true, ..hosting() }), ConfigError::ConflictingOptions("--ready", "--host"));
+        assert_eq!(rejected(Config { script, ..hosting() }), ConfigError::ConflictingOptions("--script", "--host"));
+        assert_eq!(rejected(Config { hold_result: true, ..hosting() }), ConfigError::ConflictingOptions("--hold-result", "--host"));
+        assert_eq!(rejected(Config { drill: true, ..hosting() }), ConfigError::ConflictingOptions("--drill", "--host"));
+        assert_eq!(
+            rejected(Config { start_order: StartOrder::Random, ..hosting() }),
+            ConfigError::ConflictingOptions("--random-start", "--host"),
+        );
+    }
+
+    /// Returns the config that the options build.
+    fn parse(args: &[&str]) -> Config {
+        Config::from_args(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    /// Returns the config loaded back from a file that the config was dumped to.
+    fn dump_and_load(config: &Config, name: &str) -> Config {
+        let path = std::env::temp_dir().join(format!("tic-tac-toe-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, config.to_toml()).unwrap();
+        let loaded = Config::from_args(vec!["--config".to_string(), path.to_string_lossy().into_owned()]);
+        std::fs::remove_file(&path).ok();
+        loaded.unwrap()
+    }
+
+    #[test]
+    fn a_dumped_config_loads_back_the_same() {
+        let configs = [
+            Config::default(),
+            parse(&[
+                "--size", "5", "--win-length", "4", "--no-anti-diagonal", "--board", "X...........O............",
+                "--x-name", "Ann \"the Hammer\" O'Neil", "--o-name", "Bob  \\ Two\tTabs",
+                "--ai", "--blunder", "0.25", "--explain", "--engine", "my engine --level 3", "--show-lost",
+                "--think", "750", "--clock", "90", "--max-moves", "20", "--tie-breaker", "around", "--alternate",
+                "--glyphs", "--smooth", "--shadow", "3", "--gap", "4", "--seed", "42", "--best-of", "5",
+            ]),
+            parse(&["--win", "block", "--size", "4", "--grow", "6", "--host", "4000", "--occupied", "warn", "--hex"]),
+        ];
+        for (number, config) in configs.iter().enumerate() {
+            let config = Config { seed: Some(config.seed()), ..config.clone() };
+            assert_eq!(dump_and_load(&config, &format!("config-{}", number)), config);
+        }
+    }
+
+    #[test]
+    fn a_dumped_config_always_has_a_seed() {
+        let loaded = dump_and_load(&Config::default(), "seed");
+        assert!(loaded.seed.is_some());
+        assert_eq!(Config { seed: None, ..loaded }, Config::default());
+    }
+
+    #[test]
+    fn options_after_a_config_file_override_it() {
+        let path = std::env::temp_dir().join(format!("tic-tac-toe-override-{}.toml", std::process::id()));
+        std::fs::write(&path, parse(&["--size", "4", "--x-name", "Ann"]).to_toml()).unwrap();
+        let args = vec!["--config".to_string(), path.to_string_lossy().into_owned(), "--size".to_string(), "5".to_string()];
+        let loaded = Config::from_args(args);
+        std::fs::remove_file(&path).ok();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.rules.size, 5);
+        assert_eq!(loaded.names[0], "Ann");
+    }
+
+    #[test]
+    fn quoted_text_reads_back_as_written() {
+        for text in ["", "plain", "two  spaces", "\"quoted\"", "back\\slash", "tab\tand\nnewline"] {
+            assert_eq!(unquote(&quote(text)).as_deref(), Some(text));
+        }
+        assert_eq!(unquote("\"unfinished"), None);
+        assert_eq!(unquote("\"a \" b\""), None);
+    }
+
+    #[test]
+    fn illegal_boards_are_rejected() {
+        let args = |board: &str| Config::from_args(vec!["--board".to_string(), board.to_string()]);
+        assert_eq!(args("XXX......"), Err(ConfigError::IllegalBoard("XXX......".to_string())));
+        assert_eq!(args("XXXOO.O.."), Err(ConfigError::IllegalBoard("XXXOO.O..".to_string())));
+        assert_eq!(args("X...O...."), Ok(Config { board: parse_board("X...O....", 3), ..Config::default() }));
+    }
+
+    #[test]
+    fn bad_flag_values_name_the_flag() {
+        let args = |args: &[&str]| Config::from_args(args.iter().map(|arg| arg.to_string()));
+        let size = (MAX_COLUMNS + 1).to_string();
+        assert_eq!(
+            args(&["--size", &size]),
+            Err(ConfigError::OutOfRange { flag: "--size", value: MAX_COLUMNS + 1, min: 1, max: MAX_COLUMNS }),
+        );
+        assert_eq!(args(&["--win-length", "5"]), Err(ConfigError::WinLengthTooLong { win_length: 5, size: 3 }));
+    }
+}
@@ -0,0 +1,124 @@
+//! Playing against another program, which is sent each board it has to move on and answers with its move. The board
+//! goes to its standard input as one line written row by row with X, O, and ., the same as --board takes, and the move
+//! comes back on its standard output as a line with the index of the square, counting from zero, or its name like b2.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tic_tac_toe::board::{format_board, Square};
+use tic_tac_toe::notation::parse_square;
+
+/// What the engine is being asked, shared with the thread that reads its answers.
+#[derive(Default)]
+struct Asked {
+    /// The board the engine was last sent, and where its move on it goes.
+    latest: Option<(Vec<Square>, Sender<Option<usize>>)>,
+    /// The number of boards sent that have not been answered yet. Only the answer to the last one is wanted.
+    pending: usize,
+    /// Whether the engine has closed its output, so that it will not answer again.
+    closed: bool,
+}
+
+/// A running engine.
+pub struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    asked: Arc<Mutex<Asked>>,
+}
+
+impl Engine {
+    /// Starts the engine with the given command, run by the shell so that it can have arguments.
+    pub fn spawn(command: &str) -> io::Result<Self> {
+        let mut child = shell(command).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let stdin = child.stdin.take().ok_or(ErrorKind::BrokenPipe)?;
+        let stdout = child.stdout.take().ok_or(ErrorKind::BrokenPipe)?;
+        let asked = Arc::new(Mutex::new(Asked::default()));
+        let shared = Arc::clone(&asked);
+        // Answers are read on another thread, so that waiting for them never holds up the window.
+        thread::spawn(move || read_answers(stdout, &shared));
+        Ok(Self { child, stdin, asked })
+    }
+
+    /// Sends the engine a board to move on, returning where its move will arrive once it answers. The channel is
+    /// closed without a move if the engine answers with something that is not an empty square, which is printed, or
+    /// if it stops. An error means it already has.
+    pub fn ask(&mut self, squares: &[Square]) -> io::Result<Receiver<Option<usize>>> {
+        let (sender, receiver) = mpsc::channel();
+        let mut asked = self.asked.lock().unwrap();
+        if asked.closed {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        writeln!(self.stdin, "{}", format_board(squares))?;
+        self.stdin.flush()?;
+        asked.latest = Some((squares.to_vec(), sender));
+        asked.pending += 1;
+        Ok(receiver)
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        // The engine may be in the middle of thinking, so it is stopped rather than waited for.
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Passes each answer the engine writes on to whoever asked for it last, until the engine closes its output.
+fn read_answers(stdout: ChildStdout, asked: &Mutex<Asked>) {
+    for line in BufReader::new(stdout).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let mut asked = asked.lock().unwrap();
+        // A line the engine writes without being asked is not an answer to anything.
+        if asked.pending == 0 {
+            continue;
+        }
+        asked.pending -= 1;
+        if asked.pending > 0 {
+            continue;
+        }
+        if let Some((squares, sender)) = asked.latest.take() {
+            let size = (squares.len() as f64).sqrt().round() as usize;
+            match parse_answer(&line, size).filter(|&index| squares[index] == Square::Empty) {
+                // Nobody is listening any more if the board changed since, so a failed send is fine.
+                Some(index) => {
+                    sender.send(Some(index)).ok();
+                },
+                None => eprintln!("error: the engine answered '{}', which is not an empty square on the board", line.trim()),
+            }
+        }
+    }
+    let mut asked = asked.lock().unwrap();
+    asked.closed = true;
+    asked.latest = None;
+}
+
+/// Returns the square an engine's answer names, either by index or by name, or None if it names no square on a board
+/// of the given size.
+fn parse_answer(answer: &str, size: usize) -> Option<usize> {
+    let answer = answer.trim();
+    match answer.parse::<usize>() {
+        Ok(index) if index < size * size => Some(index),
+        Ok(_) => None,
+        Err(_) => parse_square(answer, size),
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
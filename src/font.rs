@@ -0,0 +1,180 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use std::path::Path;
+
+/// The width of a glyph, in font pixels.
+pub const GLYPH_WIDTH: u32 = 5;
+
+/// The height of a glyph, in font pixels.
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// The space left between two glyphs, in font pixels.
+const GLYPH_SPACING: u32 = 1;
+
+/// Returns the rows of the glyph for the given character, top row first, with the leftmost pixel as the highest bit.
+/// Letters are drawn as uppercase, and characters without a glyph are drawn as blank space.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '"' => [0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '\'' => [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        '*' => [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        '<' => [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '>' => [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000],
+        '?' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+        '[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+        ']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        _ => [0; GLYPH_HEIGHT as usize],
+    }
+}
+
+/// Loads the TrueType font at the given path to draw all text with, in place of the built-in one.
+#[cfg(feature = "ttf")]
+pub fn load(path: &Path) -> Result<(), String> {
+    ttf::load(path)
+}
+
+/// Loads the TrueType font at the given path to draw all text with, which this build cannot do.
+#[cfg(not(feature = "ttf"))]
+pub fn load(_path: &Path) -> Result<(), String> {
+    Err("TrueType fonts need the game to be built with the 'ttf' feature".to_string())
+}
+
+/// Returns the width of the given text at the given scale, in pixels.
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    #[cfg(feature = "ttf")]
+    if let Some(width) = ttf::width(text, GLYPH_HEIGHT * scale) {
+        return width;
+    }
+    let chars = text.chars().count() as u32;
+    if chars == 0 {
+        return 0;
+    }
+    (chars * (GLYPH_WIDTH + GLYPH_SPACING) - GLYPH_SPACING) * scale
+}
+
+/// Draws the text with its top-left corner at the given coordinates.
+/// Each font pixel is drawn as a scale by scale square.
+pub fn draw_text(canvas: &mut WindowCanvas, text: &str, x: i32, y: i32, scale: u32, color: Color) {
+    #[cfg(feature = "ttf")]
+    if ttf::draw(canvas, text, x, y, GLYPH_HEIGHT * scale, color) {
+        return;
+    }
+    canvas.set_draw_color(color);
+    for (n, c) in text.chars().enumerate() {
+        let glyph_x = x + (n as u32 * (GLYPH_WIDTH + GLYPH_SPACING) * scale) as i32;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    let pixel = Rect::new(glyph_x + (col * scale) as i32, y + (row as u32 * scale) as i32, scale, scale);
+                    canvas.fill_rect(pixel).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Text drawn with a loaded TrueType font, scaled to the height the built-in font would have.
+#[cfg(feature = "ttf")]
+mod ttf {
+    use sdl2::pixels::Color;
+    use sdl2::rect::Rect;
+    use sdl2::render::WindowCanvas;
+    use sdl2::ttf::{Font, Sdl2TtfContext};
+    use std::cell::RefCell;
+    use std::path::Path;
+
+    /// The point size fonts are loaded at, which is large enough that text is scaled down rather than up.
+    const POINT_SIZE: u16 = 48;
+
+    thread_local! {
+        static FONT: RefCell<Option<Font<'static, 'static>>> = const { RefCell::new(None) };
+    }
+
+    pub fn load(path: &Path) -> Result<(), String> {
+        // The font is kept for the rest of the program, so the context it needs is never freed.
+        let context: &'static Sdl2TtfContext = Box::leak(Box::new(sdl2::ttf::init().map_err(|e| e.to_string())?));
+        let font = context.load_font(path, POINT_SIZE)?;
+        FONT.with(|loaded| *loaded.borrow_mut() = Some(font));
+        Ok(())
+    }
+
+    /// Returns the width of the text drawn the given number of pixels tall, or None if no font is loaded.
+    pub fn width(text: &str, height: u32) -> Option<u32> {
+        FONT.with(|loaded| {
+            let loaded = loaded.borrow();
+            let (width, font_height) = loaded.as_ref()?.size_of(text).unwrap_or((0, 1));
+            Some(width * height / font_height.max(1))
+        })
+    }
+
+    /// Draws the text the given number of pixels tall, returning false if no font is loaded.
+    pub fn draw(canvas: &mut WindowCanvas, text: &str, x: i32, y: i32, height: u32, color: Color) -> bool {
+        FONT.with(|loaded| {
+            let loaded = loaded.borrow();
+            let font = match loaded.as_ref() {
+                Some(font) => font,
+                None => return false,
+            };
+            // Text that cannot be rendered, like an empty string, is simply left out.
+            if let Ok(surface) = font.render(text).blended(color) {
+                let creator = canvas.texture_creator();
+                let texture = creator.create_texture_from_surface(&surface);
+                if let Ok(texture) = texture {
+                    let width = surface.width() * height / surface.height().max(1);
+                    canvas.copy(&texture, None, Rect::new(x, y, width, height)).ok();
+                }
+            }
+            true
+        })
+    }
+}
@@ -0,0 +1,142 @@
+//! A game played one move at a time, for front ends that only need to pass moves in and show the board.
+//!
+//! ```
+//! use tic_tac_toe::board::{Rules, Square};
+//! use tic_tac_toe::game::{Game, MoveOutcome};
+//!
+//! let mut game = Game::new(Rules::default());
+//! for index in [0, 3, 1, 4] {
+//!     assert_eq!(game.play(index), Ok(MoveOutcome::Ongoing));
+//! }
+//! assert_eq!(game.play(2), Ok(MoveOutcome::Won(Square::X)));
+//! ```
+
+use crate::board::{get_winner, legal_moves, next_mark, winning_lines, Line, Rules, Square};
+use std::fmt;
+
+/// What a move led to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// The game goes on.
+    Ongoing,
+    /// The move completed a line for the given player, who wins.
+    Won(Square),
+    /// The move filled the board without anyone winning.
+    Draw,
+}
+
+impl MoveOutcome {
+    /// Returns whether the game is over.
+    pub fn is_over(self) -> bool {
+        self != MoveOutcome::Ongoing
+    }
+
+    /// Returns the winner, if the move won the game.
+    pub fn winner(self) -> Option<Square> {
+        match self {
+            MoveOutcome::Won(winner) => Some(winner),
+            _ => None,
+        }
+    }
+}
+
+/// A move that cannot be played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// There is no square with the given index on the board.
+    OutOfRange(usize),
+    /// The square with the given index already has a mark in it.
+    Taken(usize),
+    /// The game is already over.
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::OutOfRange(index) => write!(f, "there is no square {} on the board", index),
+            MoveError::Taken(index) => write!(f, "square {} is already taken", index),
+            MoveError::GameOver => write!(f, "the game is already over"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// A game from the empty board, in which X moves first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    rules: Rules,
+    lines: Vec<Line>,
+    squares: Vec<Square>,
+}
+
+impl Game {
+    /// Returns a new game on an empty board under the given rules.
+    pub fn new(rules: Rules) -> Self {
+        Self { lines: winning_lines(&rules), squares: vec![Square::Empty; rules.squares()], rules }
+    }
+
+    /// Returns the rules the game is played under.
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Returns the squares of the board, row by row.
+    pub fn board(&self) -> &[Square] {
+        &self.squares
+    }
+
+    /// Returns the player whose turn it is. This is still worked out once the game is over, though nobody can move.
+    pub fn current_player(&self) -> Square {
+        next_mark(&self.squares)
+    }
+
+    /// Returns the indices of the squares the current player can play in, which is none once the game is over.
+    ///
+    /// ```
+    /// use tic_tac_toe::board::Rules;
+    /// use tic_tac_toe::game::Game;
+    ///
+    /// let mut game = Game::new(Rules::default());
+    /// game.play(4).unwrap();
+    /// assert_eq!(game.legal_moves(), vec![0, 1, 2, 3, 5, 6, 7, 8]);
+    /// ```
+    pub fn legal_moves(&self) -> Vec<usize> {
+        legal_moves(&self.squares, &self.lines)
+    }
+
+    /// Plays the current player's mark in the square with the given index, returning what the move led to. A move
+    /// that cannot be played leaves the game as it was.
+    ///
+    /// ```
+    /// use tic_tac_toe::board::Rules;
+    /// use tic_tac_toe::game::{Game, MoveError};
+    ///
+    /// let mut game = Game::new(Rules::default());
+    /// game.play(0).unwrap();
+    /// assert_eq!(game.play(0), Err(MoveError::Taken(0)));
+    /// assert_eq!(game.play(9), Err(MoveError::OutOfRange(9)));
+    /// ```
+    pub fn play(&mut self, index: usize) -> Result<MoveOutcome, MoveError> {
+        match self.squares.get(index) {
+            None => return Err(MoveError::OutOfRange(index)),
+            Some(Square::Empty) => {},
+            Some(_) => return Err(MoveError::Taken(index)),
+        }
+        if self.outcome().is_over() {
+            return Err(MoveError::GameOver);
+        }
+        self.squares[index] = self.current_player();
+        Ok(self.outcome())
+    }
+
+    /// Returns where the game stands.
+    fn outcome(&self) -> MoveOutcome {
+        match get_winner(&self.squares, &self.lines) {
+            Some(winner) => MoveOutcome::Won(winner),
+            None if !self.squares.contains(&Square::Empty) => MoveOutcome::Draw,
+            None => MoveOutcome::Ongoing,
+        }
+    }
+}
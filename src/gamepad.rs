@@ -0,0 +1,94 @@
+//! Playing with a game controller: the d-pad or left stick moves a cursor over the board, A plays in the square under
+//! it, B takes back a move, and Start begins a new game.
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+
+/// How far the stick has to be pushed from the middle to move the cursor, out of 32767.
+const STICK_PUSH: u16 = 16_000;
+
+/// How far back towards the middle the stick has to come before it can move the cursor again, out of 32767.
+const STICK_RELEASE: u16 = 8_000;
+
+/// The controllers that are plugged in, which have to be kept open for their events to arrive.
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    open: Vec<GameController>,
+}
+
+impl Controllers {
+    pub fn new(subsystem: GameControllerSubsystem) -> Self {
+        Self { subsystem, open: Vec::new() }
+    }
+
+    /// Opens controllers as they are plugged in and lets go of them as they are unplugged, returning a message to show
+    /// if one was.
+    pub fn handle(&mut self, event: &Event) -> Option<String> {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => match self.subsystem.open(which) {
+                Ok(controller) => {
+                    let message = format!("{} connected", controller.name());
+                    self.open.push(controller);
+                    Some(message)
+                },
+                Err(e) => {
+                    eprintln!("error: cannot open controller: {}", e);
+                    None
+                },
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.open.retain(|controller| controller.instance_id() != which);
+                Some("controller disconnected".to_string())
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Turns left stick motion into single steps, so that holding the stick over moves the cursor only once.
+#[derive(Default)]
+pub struct Stick {
+    /// Whether the stick is being held over across each axis, left to right and then up and down.
+    held: [bool; 2],
+}
+
+impl Stick {
+    /// Returns the step the cursor takes for the stick motion, as columns and rows, if it takes one.
+    pub fn step(&mut self, axis: Axis, value: i16) -> Option<(i32, i32)> {
+        let (held, step) = match axis {
+            Axis::LeftX => (&mut self.held[0], (1, 0)),
+            Axis::LeftY => (&mut self.held[1], (0, 1)),
+            _ => return None,
+        };
+        let distance = value.unsigned_abs();
+        if distance < STICK_RELEASE {
+            *held = false;
+        }
+        if *held || distance < STICK_PUSH {
+            return None;
+        }
+        *held = true;
+        let sign = value.signum() as i32;
+        Some((step.0 * sign, step.1 * sign))
+    }
+}
+
+/// Returns the step the cursor takes for the button, as columns and rows, if it is on the d-pad.
+pub fn dpad_step(button: Button) -> Option<(i32, i32)> {
+    match button {
+        Button::DPadUp => Some((0, -1)),
+        Button::DPadDown => Some((0, 1)),
+        Button::DPadLeft => Some((-1, 0)),
+        Button::DPadRight => Some((1, 0)),
+        _ => None,
+    }
+}
+
+/// Returns the square one step away from the given one on a board of the given size, stopping at the edges.
+pub fn move_cursor(size: usize, from: usize, (columns, rows): (i32, i32)) -> usize {
+    let last = size as i32 - 1;
+    let col = ((from % size) as i32 + columns).clamp(0, last);
+    let row = ((from / size) as i32 + rows).clamp(0, last);
+    row as usize * size + col as usize
+}
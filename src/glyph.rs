@@ -0,0 +1,191 @@
+//! Marks drawn as the letters X and O, for players who prefer them to filled squares.
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, WindowCanvas};
+use tic_tac_toe::board::Square;
+
+/// The share of the cell that a glyph covers, across.
+const GLYPH_FRACTION: f64 = 0.6;
+
+/// The thinnest stroke a glyph is drawn with, in pixels.
+const MIN_STROKE: u32 = 2;
+
+/// How many times bigger a smoothed glyph is drawn before it is scaled down to its cell.
+const SUPERSAMPLE: u32 = 2;
+
+/// A dark copy of a glyph drawn behind it, down and to the right, for a bit of depth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shadow {
+    /// How far the shadow is moved in each direction, in pixels.
+    pub offset: u32,
+    /// The opacity of the shadow.
+    pub alpha: u8,
+}
+
+/// Returns the stroke width for glyphs in cells of the given size, in pixels, so that marks look the same on any board.
+pub fn stroke_width(cell: u32) -> u32 {
+    (cell / 16).max(MIN_STROKE)
+}
+
+/// Draws the glyph for the mark centered on the given point, sized to fit a cell of the given size.
+/// The stroke width follows the cell size unless it is given. A shadow is moved no further than the margin around the
+/// glyph, so that it stays inside the cell.
+pub fn draw_glyph(canvas: &mut WindowCanvas, mark: Square, center: Point, cell: u32, stroke: Option<u32>, shadow: Option<Shadow>, color: Color) {
+    let half = cell as f64 * GLYPH_FRACTION / 2.0;
+    let stroke = stroke.unwrap_or_else(|| stroke_width(cell)) as f64;
+    if let Some(shadow) = shadow {
+        let margin = (cell as f64 / 2.0 - half).max(0.0) as i32 - 1;
+        let offset = (shadow.offset as i32).min(margin);
+        if offset > 0 {
+            // The shadow fades with the mark, so that blindfold marks do not leave it behind.
+            let alpha = (shadow.alpha as u32 * color.a as u32 / 255) as u8;
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, alpha));
+            draw_mark(canvas, mark, center.offset(offset, offset), half, stroke);
+        }
+    }
+    canvas.set_draw_color(color);
+    draw_mark(canvas, mark, center, half, stroke);
+}
+
+/// Draws the glyph like `draw_glyph`, but at a bigger size onto a texture that is then scaled down onto the cell, which
+/// blends its edges into the cell instead of leaving them jagged. It is drawn directly if the renderer cannot draw onto
+/// textures.
+pub fn draw_glyph_smooth(canvas: &mut WindowCanvas, mark: Square, center: Point, cell: u32, stroke: Option<u32>, shadow: Option<Shadow>, color: Color) {
+    let size = cell * SUPERSAMPLE;
+    let creator = canvas.texture_creator();
+    // Textures are scaled with the quality set when they are made, and the default does not blend pixels at all.
+    sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "linear");
+    let mut texture = match creator.create_texture_target(PixelFormatEnum::RGBA8888, size, size) {
+        Ok(texture) if canvas.render_target_supported() => texture,
+        _ => return draw_glyph(canvas, mark, center, cell, stroke, shadow, color),
+    };
+    texture.set_blend_mode(BlendMode::Blend);
+    // The glyph is drawn opaque and faded as a whole, so that a faded mark does not show its shadow through itself.
+    texture.set_alpha_mod(color.a);
+    let stroke = stroke.unwrap_or_else(|| stroke_width(cell)) * SUPERSAMPLE;
+    let shadow = shadow.map(|shadow| Shadow { offset: shadow.offset * SUPERSAMPLE, ..shadow });
+    let drawn = canvas.with_texture_canvas(&mut texture, |target| {
+        // The clear color matches the mark, so that its edges do not darken as they blend into the empty pixels.
+        target.set_draw_color(Color::RGBA(color.r, color.g, color.b, 0));
+        target.clear();
+        // Pixels are written as they are rather than blended, so that the shadow stays black over the cleared texture.
+        let blend_mode = target.blend_mode();
+        target.set_blend_mode(BlendMode::None);
+        let middle = (size / 2) as i32;
+        draw_glyph(target, mark, Point::new(middle, middle), size, Some(stroke), shadow, Color::RGB(color.r, color.g, color.b));
+        target.set_blend_mode(blend_mode);
+    });
+    if drawn.is_ok() {
+        canvas.copy(&texture, None, Rect::from_center(center, cell, cell)).ok();
+    }
+}
+
+/// A run of pixels on one row of a glyph, relative to its center: the row, then the first and last columns.
+type Span = (i32, i32, i32);
+
+/// Draws the shape of the mark in the current draw color.
+fn draw_mark(canvas: &mut WindowCanvas, mark: Square, center: Point, half: f64, stroke: f64) {
+    let spans = match mark {
+        Square::X => x_spans(half, stroke),
+        Square::O => o_spans(half, stroke),
+        Square::Empty => Vec::new(),
+    };
+    for (dy, from, to) in spans {
+        canvas.fill_rect(Rect::new(center.x() + from, center.y() + dy, (to - from + 1) as u32, 1)).unwrap();
+    }
+}
+
+/// Adds the pixels from one x coordinate to another on a single row, relative to the center. Both ends are rounded away
+/// from the center alike, so that a glyph is as wide on each side of it.
+fn push_span(spans: &mut Vec<Span>, dy: i32, from: f64, to: f64) {
+    let from = from.round() as i32;
+    let to = to.round() as i32;
+    if to >= from {
+        spans.push((dy, from, to));
+    }
+}
+
+/// Returns the rows of an X, as two diagonal bands that each cross the row once.
+fn x_spans(half: f64, stroke: f64) -> Vec<Span> {
+    let mut spans = Vec::new();
+    // A diagonal band of the given width is wider than that along a row, by a factor of the square root of two.
+    let half_span = stroke / std::f64::consts::SQRT_2;
+    let reach = half.round() as i32;
+    for dy in -reach..=reach {
+        let y = dy as f64;
+        let left = (-y.abs() - half_span).max(-half);
+        let right = (y.abs() + half_span).min(half);
+        if y.abs() <= half_span {
+            // Near the middle the two bands overlap into one.
+            push_span(&mut spans, dy, left, right);
+        } else {
+            push_span(&mut spans, dy, left, -y.abs() + half_span);
+            push_span(&mut spans, dy, y.abs() - half_span, right);
+        }
+    }
+    spans
+}
+
+/// Returns the rows of an O, as the part of each row between the outer and inner circles.
+fn o_spans(radius: f64, stroke: f64) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let inner = (radius - stroke).max(0.0);
+    let reach = radius.round() as i32;
+    for dy in -reach..=reach {
+        let y = dy as f64;
+        let outer_span = (radius * radius - y * y).max(0.0).sqrt();
+        if outer_span < 0.5 {
+            // The very top and bottom rows would only be a lone pixel sticking out of the ring.
+            continue;
+        }
+        if y.abs() >= inner {
+            push_span(&mut spans, dy, -outer_span, outer_span);
+        } else {
+            let inner_span = (inner * inner - y * y).sqrt();
+            push_span(&mut spans, dy, -outer_span, -inner_span);
+            push_span(&mut spans, dy, inner_span, outer_span);
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the leftmost, rightmost, top, and bottom pixels of the spans, relative to the center.
+    fn bounds(spans: &[Span]) -> (i32, i32, i32, i32) {
+        let left = spans.iter().map(|&(_, from, _)| from).min().unwrap();
+        let right = spans.iter().map(|&(_, _, to)| to).max().unwrap();
+        let top = spans.iter().map(|&(dy, _, _)| dy).min().unwrap();
+        let bottom = spans.iter().map(|&(dy, _, _)| dy).max().unwrap();
+        (left, right, top, bottom)
+    }
+
+    #[test]
+    fn glyphs_are_centered_on_any_cell_size() {
+        for cell in 8..=160 {
+            let half = cell as f64 * GLYPH_FRACTION / 2.0;
+            let stroke = stroke_width(cell) as f64;
+            for spans in [x_spans(half, stroke), o_spans(half, stroke)] {
+                let (left, right, top, bottom) = bounds(&spans);
+                assert_eq!(left, -right, "cell {}", cell);
+                assert_eq!(top, -bottom, "cell {}", cell);
+            }
+        }
+    }
+
+    #[test]
+    fn glyphs_fit_within_their_share_of_the_cell() {
+        for cell in [9, 10, 64, 65] {
+            let half = cell as f64 * GLYPH_FRACTION / 2.0;
+            let stroke = stroke_width(cell) as f64;
+            for spans in [x_spans(half, stroke), o_spans(half, stroke)] {
+                let (left, right, top, bottom) = bounds(&spans);
+                assert!(right - left < cell as i32 && bottom - top < cell as i32, "cell {}", cell);
+                assert!(right <= half.round() as i32 && bottom <= half.round() as i32, "cell {}", cell);
+            }
+        }
+    }
+}
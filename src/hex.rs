@@ -0,0 +1,135 @@
+//! Layout of the hexagonal board. Cells are pointy-topped hexagons, and each row is shifted half a cell to the right of
+//! the one above it, so the board forms a rhombus in which rows, columns, and the anti-diagonal follow the hex axes.
+
+use crate::{PLAYING_AREA_OFFSET, PLAYING_AREA_SIZE};
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use sdl2::render::WindowCanvas;
+
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+/// Returns the distance from the center of a cell to each of its corners on a board of the given size, in pixels.
+pub fn cell_radius(size: usize) -> f64 {
+    let n = size as f64;
+    let fit_width = PLAYING_AREA_SIZE as f64 / (SQRT_3 * (3.0 * n - 1.0) / 2.0);
+    let fit_height = PLAYING_AREA_SIZE as f64 / (2.0 + 1.5 * (n - 1.0));
+    fit_width.min(fit_height)
+}
+
+/// Returns the center of the top-left cell, which places the whole board in the middle of the playing area.
+fn origin(size: usize) -> (f64, f64) {
+    let n = size as f64;
+    let radius = cell_radius(size);
+    let width = SQRT_3 * radius * (3.0 * n - 1.0) / 2.0;
+    let height = radius * (2.0 + 1.5 * (n - 1.0));
+    let x = PLAYING_AREA_OFFSET as f64 + (PLAYING_AREA_SIZE as f64 - width) / 2.0 + SQRT_3 * radius / 2.0;
+    let y = PLAYING_AREA_OFFSET as f64 + (PLAYING_AREA_SIZE as f64 - height) / 2.0 + radius;
+    (x, y)
+}
+
+/// Returns the center of the cell with the given index, in pixels.
+pub fn center(size: usize, index: usize) -> (f64, f64) {
+    let row = (index / size) as f64;
+    let col = (index % size) as f64;
+    let radius = cell_radius(size);
+    let (x, y) = origin(size);
+    (x + SQRT_3 * radius * (col + row / 2.0), y + 1.5 * radius * row)
+}
+
+/// Returns the cell number that the given coordinates lie within, or None if outside the board.
+pub fn square_from_coords(size: usize, x: i32, y: i32) -> Option<usize> {
+    let radius = cell_radius(size);
+    let (origin_x, origin_y) = origin(size);
+    let (x, y) = (x as f64 - origin_x, y as f64 - origin_y);
+
+    // Convert to fractional axial coordinates, then round to the nearest cell in cube coordinates.
+    let q = (SQRT_3 / 3.0 * x - y / 3.0) / radius;
+    let r = (2.0 / 3.0 * y) / radius;
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+
+    let n = size as f64;
+    if rq < 0.0 || rr < 0.0 || rq >= n || rr >= n {
+        return None;
+    }
+    Some((rr as usize * size) + rq as usize)
+}
+
+/// Returns the corners of a hexagon with the given center and radius, starting from the top and going clockwise.
+fn corners(center: (f64, f64), radius: f64) -> Vec<Point> {
+    (0..=6)
+        .map(|i| {
+            let angle = std::f64::consts::PI / 3.0 * i as f64 - std::f64::consts::FRAC_PI_2;
+            Point::new((center.0 + radius * angle.cos()).round() as i32, (center.1 + radius * angle.sin()).round() as i32)
+        })
+        .collect()
+}
+
+/// Fills a hexagon one horizontal line at a time.
+fn fill_hexagon(canvas: &mut WindowCanvas, center: (f64, f64), radius: f64, color: Color) {
+    canvas.set_draw_color(color);
+    let half_width = SQRT_3 * radius / 2.0;
+    for dy in -(radius as i32)..=(radius as i32) {
+        let distance = (dy as f64).abs();
+        let width = if distance <= radius / 2.0 {
+            half_width
+        } else {
+            half_width * (radius - distance) / (radius / 2.0)
+        };
+        let y = center.1.round() as i32 + dy;
+        let from = Point::new((center.0 - width).round() as i32, y);
+        let to = Point::new((center.0 + width).round() as i32, y);
+        canvas.draw_line(from, to).unwrap();
+    }
+}
+
+/// Draws the outline of a cell, filling its inside with the given color if it has a mark.
+pub fn draw_cell(canvas: &mut WindowCanvas, size: usize, index: usize, fill: Option<Color>, outline: Color) {
+    let center = center(size, index);
+    let radius = cell_radius(size);
+    if let Some(color) = fill {
+        fill_hexagon(canvas, center, radius - 2.0, color);
+    }
+    canvas.set_draw_color(outline);
+    canvas.draw_lines(corners(center, radius).as_slice()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tic_tac_toe::board::{winning_lines, Rules};
+
+    #[test]
+    fn the_center_of_each_cell_is_in_that_cell() {
+        for size in 1..=9 {
+            for index in 0..size * size {
+                let (x, y) = center(size, index);
+                assert_eq!(square_from_coords(size, x.round() as i32, y.round() as i32), Some(index), "size {}", size);
+            }
+        }
+    }
+
+    #[test]
+    fn points_off_the_board_are_in_no_cell() {
+        assert_eq!(square_from_coords(3, PLAYING_AREA_OFFSET as i32, PLAYING_AREA_OFFSET as i32), None);
+        let far_corner = (PLAYING_AREA_OFFSET + PLAYING_AREA_SIZE) as i32 - 1;
+        assert_eq!(square_from_coords(3, far_corner, PLAYING_AREA_OFFSET as i32), None);
+    }
+
+    #[test]
+    fn hex_boards_leave_out_the_diagonal() {
+        for size in 2..=5 {
+            let rules = Rules { size, win_length: size.min(3), hex: true, ..Rules::default() };
+            let lines = winning_lines(&rules);
+            assert!(lines.iter().all(|line| line.windows(2).all(|pair| pair[1] != pair[0] + size + 1)), "size {}", size);
+            let square = Rules { hex: false, ..rules };
+            assert!(winning_lines(&square).len() > lines.len());
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! A record of every finished game, kept in a file that each game is added to the end of.
+//!
+//! Each game is one line of tab-separated fields: the time it finished in seconds since 1970, the player who moved
+//! first, the winner or "draw", the board it started from, and its moves in notation.
+
+use crate::player_name;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tic_tac_toe::board::{format_board, Square};
+use tic_tac_toe::notation::encode_game;
+
+/// Adds a finished game to the end of the history file, creating the file if there is none.
+pub fn append(path: &Path, start: &[Square], first: Square, moves: &[usize], size: usize, winner: Option<Square>) -> io::Result<()> {
+    let finished = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let result = winner.map_or("draw", player_name);
+    let line = format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        finished, player_name(first), result, format_board(start), encode_game(moves, size),
+    );
+    OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+}
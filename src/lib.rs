@@ -0,0 +1,9 @@
+//! Game logic for tic-tac-toe, independent of any rendering.
+
+pub mod analysis;
+pub mod challenge;
+pub mod board;
+pub mod game;
+pub mod notation;
+pub mod rng;
+pub mod symmetry;
@@ -1,12 +1,63 @@
+#[cfg(target_arch = "wasm32")]
+compile_error!("the game window needs SDL, which wasm does not have: build just the library with --lib");
+
 extern crate sdl2;
 
-use sdl2::rect::Rect;
+mod achievement;
+mod clock;
+mod config;
+mod engine;
+mod font;
+mod gamepad;
+mod glyph;
+mod hex;
+mod history;
+mod menu;
+mod net;
+mod research;
+mod save;
+mod scoreboard;
+mod script;
+mod sound;
+mod stats;
+mod theme;
+mod toast;
+mod tournament;
+
+use sdl2::rect::{Point, Rect};
 use sdl2::pixels::Color;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::render::WindowCanvas;
-use sdl2::mouse::MouseButton;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::render::{BlendMode, WindowCanvas};
+use sdl2::clipboard::ClipboardUtil;
+use sdl2::controller::Button;
+use sdl2::mouse::{Cursor, MouseButton, SystemCursor};
+use sdl2::surface::Surface;
+use sdl2::VideoSubsystem;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
+use achievement::{Achievements, WonGame};
+use clock::Clock;
+use engine::Engine;
+use config::{Config, Network, OccupiedClick, Renderer, StartOrder, USAGE};
+use gamepad::{dpad_step, move_cursor, Controllers, Stick};
+use menu::{Menu, MenuAction};
+use net::{Connection, Message};
+use save::SaveData;
+use scoreboard::Match;
+use sound::Sounds;
+use stats::{Heatmap, SessionStats};
+use theme::{lerp_color, Theme, ThemeWatcher};
+use toast::Toasts;
+use tic_tac_toe::analysis::{best_move_cancellable, best_move_within, blunder, classify_position, classify_position_until, evaluate_moves, fork_moves, game_tree_size, move_reason, MoveReason, Outcome};
+use tic_tac_toe::challenge::encode_challenge;
+use tic_tac_toe::board::{board_from_moves, board_hash, game_over, get_square_flatten_index, is_won, next_mark, format_board, legal_moves, winning_lines, Line, Rules, Square, WinCondition};
+use tic_tac_toe::notation::{encode_game, parse_square, square_name};
+use tic_tac_toe::rng::Rng;
 
 /// The width of the outer borders of the playing area, in pixels.
 const BORDER_THICKNESS: i32 = 20;
@@ -20,48 +71,552 @@ const PLAYING_AREA_OFFSET: u32 = BORDER_THICKNESS as u32 * 2;
 /// The height and width of the playing area, in pixels.
 const PLAYING_AREA_SIZE: u32 = WINDOW_SIZE - (PLAYING_AREA_OFFSET * 2);
 
-/// The number of squares in the horizontal and vertical direction.
-const SQUARES: u32 = 3;
+/// The name the application is identified by in the taskbar and window manager.
+const APP_NAME: &str = "Tic-Tac-Toe";
+
+/// The path to the window icon, relative to the working directory.
+const ICON_PATH: &str = "assets/icon.bmp";
+
+/// The height of the countdown bar shown between games, in pixels.
+const COUNTDOWN_BAR_HEIGHT: u32 = 6;
+
+/// The time each move stays on screen when replaying a game, in milliseconds.
+const REPLAY_STEP_MILLIS: u64 = 500;
+
+/// The time between the moves of a script, in milliseconds.
+const SCRIPT_STEP_MILLIS: u64 = 300;
+
+/// The time the square played in each replay step takes to fade back from highlighted, in milliseconds.
+const REPLAY_HIGHLIGHT_MILLIS: u64 = 400;
+
+/// The opacity of the highlight on the square just played in a replay, before it fades.
+const REPLAY_HIGHLIGHT_ALPHA: u8 = 128;
+
+/// The height of each strip of a background gradient, in pixels.
+const GRADIENT_STRIP_HEIGHT: u32 = 4;
+
+/// The time after a placement during which further clicks on the same square are ignored, in milliseconds.
+const PLACEMENT_DEBOUNCE_MILLIS: u64 = 100;
+
+/// The time the scoreboard is shown between games of a match, in seconds.
+const SCOREBOARD_TIMEOUT: u64 = 3;
+
+/// The time the win effects last, in milliseconds.
+const WIN_EFFECT_MILLIS: u64 = 300;
+
+/// The time the view takes to scroll to a move made out of sight on a panned board, in milliseconds.
+const FOLLOW_MILLIS: u64 = 300;
+
+/// How far the border is tinted toward the color of the player to move, when it is.
+const TURN_TINT: f64 = 0.25;
+
+/// The time the border tint takes to fade from one player to the other, in milliseconds.
+const TURN_TINT_FADE_MILLIS: u64 = 250;
+
+/// The furthest the board moves from its place when shaking, in pixels.
+const SHAKE_PIXELS: f64 = 8.0;
+
+/// The time a mark stays fully visible in blindfold mode, in milliseconds.
+const BLINDFOLD_VISIBLE_MILLIS: u64 = 1000;
+
+/// The time a mark takes to fade away in blindfold mode, in milliseconds.
+const BLINDFOLD_FADE_MILLIS: u64 = 500;
+
+/// The size of each font pixel in the debug overlay, in screen pixels.
+const DEBUG_TEXT_SCALE: u32 = 2;
+
+/// The size of each font pixel in the tooltip under the mouse, in screen pixels.
+const TOOLTIP_TEXT_SCALE: u32 = 2;
+
+/// How far the tooltip is drawn below and to the right of the mouse, in pixels, so that the pointer does not cover it.
+const TOOLTIP_OFFSET: i32 = 16;
+
+/// The player the computer plays as when playing against it: Blue, the O mark, with the human playing Red.
+const AI_PLAYER: Square = Square::O;
+
+/// The size of each font pixel in the lost position hint, in screen pixels.
+const LOST_HINT_TEXT_SCALE: u32 = 2;
+
+/// The size of each font pixel in the opening drill score, in screen pixels.
+const DRILL_TEXT_SCALE: u32 = 2;
+
+/// The size of each font pixel in the thinking indicator, in screen pixels.
+const THINKING_TEXT_SCALE: u32 = 2;
+
+/// The time the computer can think before the indicator shows, in milliseconds, so that quick moves do not flicker it.
+const THINKING_DELAY_MILLIS: u64 = 150;
+
+/// The time each dot of the thinking indicator takes to appear, in milliseconds.
+const THINKING_DOT_MILLIS: u128 = 300;
+
+/// The size of each font pixel in the result of a game, in screen pixels.
+const RESULT_TEXT_SCALE: u32 = 3;
+
+/// The size of each font pixel on the screen where both players say they are ready, in screen pixels.
+const READY_TEXT_SCALE: u32 = 4;
+
+/// The keys Red and Blue press to say they are ready, on opposite sides of the keyboard.
+const READY_KEYS: [Keycode; 2] = [Keycode::Q, Keycode::P];
+
+/// The space above and below the result of a game on its band, in pixels.
+const RESULT_BAND_PADDING: i32 = 12;
+
+/// The size of each font pixel in the word drawn over a drawn board, in screen pixels.
+const DRAW_TEXT_SCALE: u32 = 6;
 
-/// The height and width of each square, in pixels.
-const SQUARE_SIZE: u32 = PLAYING_AREA_SIZE / SQUARES;
+/// The opacity of the gray laid over a drawn board.
+const DRAW_WASH_ALPHA: u8 = 128;
 
-/// Extra pixels to fill in so there is no space between the outer squares and the border.
-const FILL_IN: u32 = PLAYING_AREA_SIZE - (SQUARE_SIZE * SQUARES);
+/// The size of each font pixel in the win streak, in screen pixels.
+const STREAK_TEXT_SCALE: u32 = 2;
 
-/// The time to wait in between games, in seconds.
-const NEW_GAME_TIMEOUT: u64 = 2;
+/// The shortest run of wins shown as a streak, since a single win is just the last result.
+const MIN_SHOWN_STREAK: u32 = 2;
 
-/// Lambda functions to be used to detect straight line winners in get_winner().
-const STRAIGHT_LINE_LAMBDAS: [fn(usize, usize) -> (usize, usize); 2] = [
-    |constant, i| (constant, i),
-    |constant, i| (i, constant),
-];
+/// The size of each font pixel in draw offers and undo requests, in screen pixels.
+const PROMPT_TEXT_SCALE: u32 = 2;
 
-/// Lambda functions to be used to detect diagonal line winners in get_winner().
-const DIAGONAL_LINE_LAMBDAS: [fn(usize, usize) -> (usize, usize); 2] = [
-    |_, i| (i, i),
-    |_, i| (SQUARES as usize - i - 1, i),
-];
+/// The colors of the evaluation dots for moves that win, draw, and lose for the player to move.
+const EVAL_COLORS: [Color; 3] = [Color::RGB(64, 200, 64), Color::RGB(230, 200, 40), Color::RGB(220, 50, 50)];
+
+/// The share of a square that an evaluation dot covers, across.
+const EVAL_DOT_FRACTION: f64 = 0.15;
+
+/// The opacity of the board guides, drawn in the grid color.
+const GUIDE_ALPHA: u8 = 40;
+
+/// The share of a square that the center guide dot covers, across.
+const GUIDE_DOT_FRACTION: f64 = 0.08;
+
+/// The color the heatmap tints squares with, at its strongest.
+const HEATMAP_COLOR: Color = Color::RGBA(255, 96, 0, 192);
+
+/// The length of each side of the head of the hint arrow, in pixels.
+const HINT_ARROW_HEAD: f64 = 14.0;
+
+/// The opacity of each ring of the glow around the hovered square, from the outside in.
+const HOVER_GLOW_ALPHAS: [u8; 3] = [160, 96, 48];
 
 struct GameState {
     freeze_until: Option<Instant>,
     squares: Vec<Square>,
     turn: bool,
+    /// The board the game started from.
+    start: Vec<Square>,
+    /// The player who moved first from the start board.
+    first: Square,
+    /// The square indices played so far, in order.
+    history: Vec<usize>,
+    replay: Option<Replay>,
+    /// The square and time of the most recent placement.
+    last_placement: Option<(usize, Instant)>,
+    /// The winner and the time the game was won, if it was.
+    won_at: Option<(Square, Instant)>,
+    /// How the game ended, once it has: the winner, or None for a draw. It is worked out once, as the game ends, and
+    /// kept until the next game starts.
+    ended: Option<Option<Square>>,
+    /// Whoever has completed a line on the board, once it has been worked out since the board last changed.
+    board_winner: Option<Option<Square>>,
+    /// The time the first move was made.
+    started_at: Option<Instant>,
+    /// The time each square was played, if it was played in this session.
+    placed_at: Vec<Option<Instant>>,
+    /// The time each player has left, if the game is timed.
+    clock: Option<Clock>,
+    /// Whether the computer can force a win from the current position, as last worked out.
+    lost: bool,
+    /// The check of whether the computer can force a win, while the lost position hint is shown.
+    lost_check: Option<Thinking<bool>>,
+    /// The player who offered a draw that has not been answered yet, in a game over the network.
+    draw_offer: Option<Square>,
+    /// Whether both players agreed to a draw.
+    draw_agreed: bool,
+    /// The player who asked to take back their last move and has not been answered yet, in a game over the network.
+    undo_request: Option<Square>,
+}
+
+impl GameState {
+    /// Returns a game that starts from the given board.
+    fn from_board(squares: Vec<Square>, clock: Option<Duration>) -> Self {
+        let empty = Self::new(squares.len(), clock, next_mark(&squares));
+        Self {
+            start: squares.clone(),
+            squares,
+            ..empty
+        }
+    }
+
+    /// Returns the squares that can be played in, which is none while the game is frozen or once it is over.
+    fn legal_moves(&self, lines: &[Line]) -> Vec<usize> {
+        if self.freeze_until.is_some() { Vec::new() } else { legal_moves(&self.squares, lines) }
+    }
+
+    /// Returns the player whose turn it is.
+    fn to_move(&self) -> Square {
+        if self.turn { Square::X } else { Square::O }
+    }
+
+    /// Returns whoever has completed a line on the board, only looking through the lines when the board has changed
+    /// since the last time.
+    fn winner(&mut self, lines: &[Line]) -> Option<Square> {
+        match self.board_winner {
+            Some(winner) => winner,
+            None => {
+                let winner = is_won(&self.squares, lines);
+                self.board_winner = Some(winner);
+                winner
+            },
+        }
+    }
+}
+
+impl GameState {
+    /// Returns the game stored in a save, restarting its timers from the given time. The clocks pick up with the time
+    /// that was left on them, and saves from before clocks were saved start a timed game with the full budget.
+    fn from_save(save: SaveData, clock: Option<Duration>, now: Instant) -> Self {
+        let squares = board_from_moves(&save.start, save.first, &save.moves);
+        let to_move = if save.moves.len().is_multiple_of(2) { save.first } else { save.first.opponent() };
+        let empty = Self::new(squares.len(), clock, save.first);
+        Self {
+            freeze_until: save.freeze_remaining.map(|remaining| now + remaining),
+            turn: to_move == Square::X,
+            squares,
+            start: save.start,
+            history: save.moves,
+            clock: match save.clock_remaining {
+                Some([x_remaining, o_remaining]) => Some(Clock::resume(x_remaining, o_remaining)),
+                None => empty.clock,
+            },
+            ..empty
+        }
+    }
+
+    /// Returns a save of the game, with its timers measured from the given time.
+    fn to_save(&self, now: Instant) -> SaveData {
+        let freeze_remaining = match &self.replay {
+            Some(replay) => Some(replay.remaining_freeze),
+            None => self.freeze_until.map(|freeze_until| freeze_until.saturating_duration_since(now)),
+        };
+        let to_move = self.to_move();
+        let clock_remaining = self.clock.as_ref().map(|clock| {
+            [Square::X, Square::O].map(|player| clock.remaining_at(player, to_move, now))
+        });
+        SaveData { start: self.start.clone(), first: self.first, moves: self.history.clone(), freeze_remaining, clock_remaining }
+    }
 }
 
-impl Default for GameState {
-    fn default() -> Self {
+impl GameState {
+    /// Returns a new game on an empty board with the given number of squares and the given player moving first, timed
+    /// with the given budget if any.
+    fn new(squares: usize, clock: Option<Duration>, first: Square) -> Self {
         Self {
             freeze_until: None,
-            squares: vec![Square::Empty; (SQUARES * SQUARES) as usize],
-            turn: true,
+            squares: vec![Square::Empty; squares],
+            turn: first == Square::X,
+            start: vec![Square::Empty; squares],
+            first,
+            history: Vec::new(),
+            replay: None,
+            last_placement: None,
+            won_at: None,
+            ended: None,
+            board_winner: None,
+            started_at: None,
+            placed_at: vec![None; squares],
+            clock: clock.map(Clock::new),
+            lost: false,
+            lost_check: None,
+            draw_offer: None,
+            undo_request: None,
+            draw_agreed: false,
+        }
+    }
+}
+
+/// Steps through a list of moves, one at a time.
+struct Replay {
+    start: Vec<Square>,
+    first: Square,
+    moves: Vec<usize>,
+    /// The number of moves currently shown.
+    shown: usize,
+    next_step: Instant,
+    /// The time the last move was shown, if any has been yet.
+    stepped_at: Option<Instant>,
+    /// The freeze time that was left when the replay started.
+    remaining_freeze: Duration,
+}
+
+impl Replay {
+    /// Returns a replay of the moves that shows its first move a step after the given time.
+    fn new(start: Vec<Square>, first: Square, moves: Vec<usize>, remaining_freeze: Duration, now: Instant) -> Self {
+        Self {
+            start,
+            first,
+            moves,
+            shown: 0,
+            next_step: now + Duration::from_millis(REPLAY_STEP_MILLIS),
+            stepped_at: None,
+            remaining_freeze,
         }
     }
+
+    /// Advances the replay if it is time for the next move. Returns false once the replay is over.
+    fn step(&mut self, now: Instant) -> bool {
+        if now < self.next_step {
+            return true;
+        }
+        if self.shown == self.moves.len() {
+            return false;
+        }
+        self.shown += 1;
+        self.next_step = now + Duration::from_millis(REPLAY_STEP_MILLIS);
+        self.stepped_at = Some(now);
+        true
+    }
+
+    /// Returns the square that changed in the last step and how far its highlight has faded, from 0.0 to 1.0, or
+    /// None once it has faded out.
+    fn changed(&self, now: Instant) -> Option<(usize, f64)> {
+        let stepped_at = self.stepped_at?;
+        let progress = (now - stepped_at).as_millis() as f64 / REPLAY_HIGHLIGHT_MILLIS as f64;
+        if progress >= 1.0 { None } else { Some((self.moves[self.shown - 1], progress)) }
+    }
+
+    /// Returns the board with the moves shown so far.
+    fn squares(&self) -> Vec<Square> {
+        board_from_moves(&self.start, self.first, &self.moves[..self.shown])
+    }
+}
+
+/// A move the computer is searching for on another thread, so that the window keeps drawing while it thinks, or some
+/// other answer about a board that takes as long. Dropping it cancels the search and waits for the thread to finish,
+/// so that a search never outlives the game.
+struct Thinking<T = Option<usize>> {
+    /// The board the answer is for. A search for a board that has since changed is given up on.
+    squares: Vec<Square>,
+    started: Instant,
+    receiver: Receiver<T>,
+    cancel: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    /// Whether the move is coming from the engine, which falls back to the computer's own search if it gives none.
+    engine: bool,
+}
+
+impl Thinking {
+    /// Starts searching for the computer's move on the given board.
+    fn start(squares: &[Square], rules: &Rules, budget: Duration, now: Instant) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (board, rules) = (squares.to_vec(), rules.clone());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::clone(&cancel);
+        // Nobody is listening any more if the search was given up on, so a failed send is fine.
+        let worker = thread::spawn(move || {
+            sender.send(best_move_cancellable(&board, AI_PLAYER, &rules, budget, &cancelled)).ok();
+        });
+        Self { squares: squares.to_vec(), started: now, receiver, cancel, worker: Some(worker), engine: false }
+    }
+
+    /// Returns a search that waits for the engine's move on the given board, which it has been sent.
+    fn from_engine(squares: &[Square], receiver: Receiver<Option<usize>>, now: Instant) -> Self {
+        Self { squares: squares.to_vec(), started: now, receiver, cancel: Arc::new(AtomicBool::new(false)), worker: None, engine: true }
+    }
+
+    /// Returns a search that has already settled on the given move, for a move that is picked without searching.
+    fn decided(squares: &[Square], square: usize, now: Instant) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        sender.send(Some(square)).ok();
+        Self { squares: squares.to_vec(), started: now, receiver, cancel: Arc::new(AtomicBool::new(false)), worker: None, engine: false }
+    }
+}
+
+impl Thinking<bool> {
+    /// Starts working out whether the computer can force a win on the given board.
+    fn lost(squares: &[Square], to_move: Square, rules: &Rules, now: Instant) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (board, rules) = (squares.to_vec(), rules.clone());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::clone(&cancel);
+        let worker = thread::spawn(move || {
+            let outcome = classify_position_until(&board, to_move, &rules, &|| cancelled.load(Ordering::Relaxed));
+            sender.send(outcome == Outcome::WinFor(AI_PLAYER)).ok();
+        });
+        Self { squares: squares.to_vec(), started: now, receiver, cancel, worker: Some(worker), engine: false }
+    }
+}
+
+impl<T> Drop for Thinking<T> {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+/// Takes back the last move, and the computer's move before it when playing against the computer, so that a player
+/// at this computer is to move again.
+fn undo(state: &mut GameState, config: &Config) {
+    while take_back(state) {
+        if !(config.ai && state.to_move() == AI_PLAYER) {
+            break;
+        }
+    }
+}
+
+/// Takes back the last move, if there is one, returning whether there was.
+fn take_back(state: &mut GameState) -> bool {
+    state.last_placement = None;
+    match state.history.pop() {
+        Some(square) => {
+            state.squares[square] = Square::Empty;
+            state.board_winner = None;
+            state.placed_at[square] = None;
+            state.turn = !state.turn;
+            true
+        },
+        None => false,
+    }
+}
+
+/// Plays the mark of the player to move in the given square. Moving instead of answering a draw offer or an undo
+/// request declines it.
+fn place(state: &mut GameState, square: usize, now: Instant) {
+    if state.draw_offer == Some(state.to_move().opponent()) {
+        state.draw_offer = None;
+    }
+    if state.undo_request == Some(state.to_move().opponent()) {
+        state.undo_request = None;
+    }
+    state.squares[square] = state.to_move();
+    state.board_winner = None;
+    state.turn = !state.turn;
+    state.history.push(square);
+    state.last_placement = Some((square, now));
+    state.started_at.get_or_insert(now);
+    state.placed_at[square] = Some(now);
+}
+
+/// Returns the player who moves first in the next game, given who moved first in the last one.
+fn next_first(order: StartOrder, last: Square, rng: &mut Rng) -> Square {
+    match order {
+        StartOrder::RedFirst => Square::X,
+        StartOrder::Alternate => last.opponent(),
+        StartOrder::Random => if rng.below(2) == 0 { Square::X } else { Square::O },
+    }
+}
+
+/// Returns the player who is not played at this computer, but by the AI or over the network.
+fn remote_player(config: &Config) -> Option<Square> {
+    match &config.network {
+        Some(Network::Host(_)) => Some(Square::O),
+        Some(Network::Connect(_)) => Some(Square::X),
+        None if config.ai => Some(AI_PLAYER),
+        None => None,
+    }
+}
+
+/// Plays in the clicked square if a player at this computer is to move, ignoring repeated clicks on the same square.
+/// A square that is already taken is handled as configured. Returns whether a mark was placed.
+fn click_square(state: &mut GameState, toasts: &mut Toasts, config: &Config, square: usize, now: Instant) -> bool {
+    let debounced = state.last_placement.is_some_and(|(last_square, time)| {
+        last_square == square && now - time < Duration::from_millis(PLACEMENT_DEBOUNCE_MILLIS)
+    });
+    let remote_turn = remote_player(config) == Some(state.to_move());
+    if debounced || remote_turn {
+        return false;
+    }
+    if state.squares[square] != Square::Empty {
+        match config.occupied {
+            OccupiedClick::Ignore => return false,
+            OccupiedClick::Warn => {
+                toasts.push("that square is taken", now);
+                return false;
+            },
+            // The old mark is cleared first, as if it had been right-clicked away.
+            OccupiedClick::Overwrite => state.history.retain(|&played| played != square),
+        }
+    }
+    place(state, square, now);
+    true
+}
+
+/// Sends a message to the other player, dropping the connection if they are gone.
+fn send_message(connection: &mut Option<Connection>, toasts: &mut Toasts, message: Message, now: Instant) {
+    if let Some(open) = connection {
+        if let Err(e) = open.send(message) {
+            eprintln!("error: {}", e);
+            toasts.push("opponent disconnected", now);
+            *connection = None;
+        }
+    }
+}
+
+/// Returns whether the given player can ask to take back the last move: it must be theirs, and nothing else can be
+/// waiting for an answer.
+fn can_request_undo(state: &GameState, player: Square) -> bool {
+    !state.history.is_empty() && state.to_move() == player.opponent() && state.draw_offer.is_none() && state.undo_request.is_none()
+}
+
+/// Applies a message from the other player, who plays the given mark, returning the answer to send back if it needs one.
+/// Moves that are not legal are ignored, and undo requests that cannot be granted are declined straight away.
+fn receive_message(state: &mut GameState, toasts: &mut Toasts, message: Message, remote: Square, lines: &[Line], now: Instant) -> Option<Message> {
+    match message {
+        Message::Move(square) => {
+            if state.to_move() == remote && state.legal_moves(lines).contains(&square) {
+                place(state, square, now);
+            }
+        },
+        Message::OfferDraw => state.draw_offer = Some(remote),
+        Message::AcceptDraw => {
+            if state.draw_offer == Some(remote.opponent()) {
+                state.draw_agreed = true;
+            }
+        },
+        Message::DeclineDraw => {
+            if state.draw_offer == Some(remote.opponent()) {
+                state.draw_offer = None;
+                toasts.push("draw declined", now);
+            }
+        },
+        Message::RequestUndo => {
+            if !can_request_undo(state, remote) {
+                return Some(Message::DeclineUndo);
+            }
+            state.undo_request = Some(remote);
+        },
+        Message::AcceptUndo => {
+            if state.undo_request == Some(remote.opponent()) {
+                state.undo_request = None;
+                take_back(state);
+                toasts.push("move taken back", now);
+            }
+        },
+        Message::DeclineUndo => {
+            if state.undo_request == Some(remote.opponent()) {
+                state.undo_request = None;
+                toasts.push("undo declined", now);
+            }
+        },
+    }
+    None
+}
+
+/// Returns what the computer says about a move it played for the given reason, talking to the player it plays against.
+fn explanation(reason: MoveReason) -> &'static str {
+    match reason {
+        MoveReason::Win => "Taking the winning line",
+        MoveReason::Block => "Blocking your threat",
+        MoveReason::Fork => "Creating a fork",
+        MoveReason::Positional => "No immediate threat, improving position",
+    }
 }
 
-#[derive(Clone, PartialEq)]
-enum Square { X, O, Empty }
+/// Returns the fixed color name of the player, "Red" or "Blue", which files and reports use so that they can be read
+/// back the same way. Text on screen uses `Config::name`, which can be a name given instead.
+fn player_name(player: Square) -> &'static str {
+    if player == Square::X { "Red" } else { "Blue" }
+}
 
 /// Fills a rectangle with the given color.
 fn fill_rectangle(canvas: &mut WindowCanvas, rectangle: Rect, color: Color) {
@@ -69,155 +624,2221 @@ fn fill_rectangle(canvas: &mut WindowCanvas, rectangle: Rect, color: Color) {
     canvas.fill_rect(rectangle).unwrap();
 }
 
-/// Returns the square number that the given coordinates lie within, or None if outside the playing area.
-fn get_square_from_coords(x: i32, y: i32) -> Option<usize> {
-    let x = x - PLAYING_AREA_OFFSET as i32;
-    let y = y - PLAYING_AREA_OFFSET as i32;
-    if x <= 0 || y <= 0 || x >= (SQUARE_SIZE * SQUARES) as i32 || y >= (SQUARE_SIZE * SQUARES) as i32 {
-        return None;
+/// Fills a rectangle with a vertical gradient from one color to another.
+fn fill_gradient(canvas: &mut WindowCanvas, rectangle: Rect, top: Color, bottom: Color) {
+    if top == bottom {
+        fill_rectangle(canvas, rectangle, top);
+        return;
+    }
+    let mut y = 0;
+    while y < rectangle.height() {
+        let height = GRADIENT_STRIP_HEIGHT.min(rectangle.height() - y);
+        let color = lerp_color(top, bottom, y as f64 / rectangle.height() as f64);
+        fill_rectangle(canvas, Rect::new(rectangle.x(), rectangle.y() + y as i32, rectangle.width(), height), color);
+        y += height;
+    }
+}
+
+/// Where the squares of a board are drawn in the window. Squares shrink to fit the whole board into the playing
+/// area, down to the smallest size allowed; a board that would need smaller squares than that is drawn bigger than the
+/// playing area, and only the part around the view shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Layout {
+    size: usize,
+    /// The height and width of each square, in pixels.
+    square_size: u32,
+    /// The top-left corner of the board, which is outside the playing area while the board is panned.
+    origin: Point,
+}
+
+impl Layout {
+    /// Returns the layout of a board of the given size with squares no smaller than the given size, if there is one.
+    /// The view is the point of the board in the middle of the playing area, as a share of the board across and down.
+    fn new(size: usize, min_square: Option<u32>, view: (f64, f64)) -> Self {
+        let square_size = (PLAYING_AREA_SIZE / size as u32).max(min_square.unwrap_or(0));
+        let board = square_size * size as u32;
+        if board <= PLAYING_AREA_SIZE {
+            // The pixels left over are split between both sides so that the board stays centered in the window.
+            let offset = (PLAYING_AREA_OFFSET + (PLAYING_AREA_SIZE - board) / 2) as i32;
+            return Self { size, square_size, origin: Point::new(offset, offset) };
+        }
+        let (x, y) = clamp_view(board, view);
+        let middle = (PLAYING_AREA_OFFSET + PLAYING_AREA_SIZE / 2) as f64;
+        let (min, max) = ((PLAYING_AREA_OFFSET + PLAYING_AREA_SIZE) as i32 - board as i32, PLAYING_AREA_OFFSET as i32);
+        let origin = Point::new(
+            ((middle - x * board as f64).round() as i32).clamp(min, max),
+            ((middle - y * board as f64).round() as i32).clamp(min, max),
+        );
+        Self { size, square_size, origin }
+    }
+
+    /// Returns the height and width of the whole board, in pixels.
+    fn board_size(&self) -> u32 {
+        self.square_size * self.size as u32
+    }
+
+    /// Returns whether the board is too big for the playing area, so that only part of it shows.
+    fn is_panned(&self) -> bool {
+        self.board_size() > PLAYING_AREA_SIZE
+    }
+
+    /// Returns the part of the window the board shows in.
+    fn area(&self) -> Rect {
+        if self.is_panned() {
+            Rect::new(PLAYING_AREA_OFFSET as i32, PLAYING_AREA_OFFSET as i32, PLAYING_AREA_SIZE, PLAYING_AREA_SIZE)
+        } else {
+            Rect::new(self.origin.x(), self.origin.y(), self.board_size(), self.board_size())
+        }
+    }
+
+    /// Returns the view moved by the given number of squares across and down, kept to where the board fills the
+    /// playing area.
+    fn pan(&self, view: (f64, f64), cols: i32, rows: i32) -> (f64, f64) {
+        let step = self.square_size as f64 / self.board_size() as f64;
+        clamp_view(self.board_size(), (view.0 + cols as f64 * step, view.1 + rows as f64 * step))
+    }
+
+    /// Returns whether the whole of the square with the given index shows in the playing area.
+    fn shows(&self, index: usize) -> bool {
+        let square = self.square_rect(index, 0);
+        self.area().contains_rect(square)
+    }
+
+    /// Returns the view with the square with the given index in the middle, or as near it as the board allows.
+    fn view_of(&self, index: usize) -> (f64, f64) {
+        let (row, col) = (index / self.size, index % self.size);
+        let view = ((col as f64 + 0.5) / self.size as f64, (row as f64 + 0.5) / self.size as f64);
+        clamp_view(self.board_size(), view)
+    }
+
+    /// Returns the rectangle the square with the given index covers, leaving the given gap between it and its
+    /// neighbours. The gap is split between both sides and never takes up more than half the square.
+    fn square_rect(&self, index: usize, gap: u32) -> Rect {
+        let gap = gap.min(self.square_size / 2);
+        let row = (index / self.size) as i32;
+        let col = (index % self.size) as i32;
+        let offset = self.origin + Point::new((gap / 2) as i32, (gap / 2) as i32);
+        let square_size = self.square_size as i32;
+        Rect::new(offset.x() + square_size * col, offset.y() + square_size * row, self.square_size - gap, self.square_size - gap)
+    }
+
+    /// Returns the square number that the given coordinates lie within, or None if outside the playing area or in the
+    /// gap between two squares.
+    fn square_at(&self, gap: u32, x: i32, y: i32) -> Option<usize> {
+        let left = x - self.origin.x();
+        let top = y - self.origin.y();
+        let board = self.board_size() as i32;
+        if left <= 0 || top <= 0 || left >= board || top >= board || !self.area().contains_point((x, y)) {
+            return None;
+        }
+        let col = left as u32 / self.square_size;
+        let row = top as u32 / self.square_size;
+        let index = (row as usize * self.size) + col as usize;
+        if gap > 0 && !self.square_rect(index, gap).contains_point((x, y)) {
+            return None;
+        }
+        Some(index)
+    }
+
+    /// Returns the center of the square with the given index.
+    fn center(&self, index: usize) -> Point {
+        let half = (self.square_size / 2) as i32;
+        self.square_rect(index, 0).top_left() + Point::new(half, half)
+    }
+}
+
+/// Returns the view kept to where a board of the given size, in pixels, fills the playing area.
+fn clamp_view(board: u32, view: (f64, f64)) -> (f64, f64) {
+    let half = (PLAYING_AREA_SIZE as f64 / 2.0 / board as f64).min(0.5);
+    (view.0.clamp(half, 1.0 - half), view.1.clamp(half, 1.0 - half))
+}
+
+/// Returns the square number that the given coordinates lie within on the board the game is played on.
+fn square_at(config: &Config, layout: &Layout, x: i32, y: i32) -> Option<usize> {
+    let rules = &config.rules;
+    if rules.hex { hex::square_from_coords(rules.size, x, y) } else { layout.square_at(config.gap, x, y) }
+}
+
+/// Returns the center of the square with the given index, in pixels.
+fn square_center(rules: &Rules, layout: &Layout, index: usize) -> Point {
+    if rules.hex {
+        let (x, y) = hex::center(rules.size, index);
+        return Point::new(x.round() as i32, y.round() as i32);
     }
-    let col = x as u32 / SQUARE_SIZE;
-    let row = y as u32 / SQUARE_SIZE;
-    Some(((row * SQUARES) + col) as usize)
+    layout.center(index)
 }
 
-/// Returns a new rect that covers the inner portion of the given rectangle.
+/// Returns a new rect that covers the inner portion of the given rectangle. A rectangle too small to have one is kept
+/// at a single pixel rather than shrunk away.
 fn get_inner_rect(rect: Rect) -> Rect {
-    let mut new = rect.clone();
+    let mut new = rect;
     new.set_x(rect.x() + 1);
     new.set_y(rect.y() + 1);
-    new.set_width(rect.width() - 2);
-    new.set_height(rect.height() - 2);
+    new.set_width(rect.width().saturating_sub(2).max(1));
+    new.set_height(rect.height().saturating_sub(2).max(1));
     new
 }
 
-/// Returns the winner of the board, or None if nobody has won yet.
-fn get_winner(squares: &Vec<Square>) -> Option<Square> {
-    for i in 0..SQUARES as usize {
-        for lambda in STRAIGHT_LINE_LAMBDAS.iter() {
-            if let Some(winner) = line_winner(&squares, *lambda, i) {
-                return Some(winner);
+/// Returns the value moved toward the target by no more than the given step.
+fn step_toward(value: f64, target: f64, step: f64) -> f64 {
+    if value < target { (value + step).min(target) } else { (value - step).max(target) }
+}
+
+/// Returns how far the border is tinted toward Red and Blue, faded from the given weights over the given time toward
+/// the player to move, or toward neither if nobody is. The fade goes by time rather than by frame, so that it takes as
+/// long at any frame rate.
+fn fade_turn_tint(weights: [f64; 2], to_move: Option<Square>, elapsed: Duration) -> [f64; 2] {
+    let step = elapsed.as_secs_f64() / Duration::from_millis(TURN_TINT_FADE_MILLIS).as_secs_f64();
+    let fade = |weight, player| step_toward(weight, if to_move == Some(player) { 1.0 } else { 0.0 }, step);
+    [fade(weights[0], Square::X), fade(weights[1], Square::O)]
+}
+
+/// Freezes the game from the given time in preparation of a new game.
+fn endgame(state: &mut GameState, delay: Duration, now: Instant) {
+    state.freeze_until = Some(now + delay)
+}
+
+/// Returns the opacity of the mark in each square in blindfold mode, where marks fade away after being placed.
+/// Marks that were not placed in this session count as placed long ago.
+fn blindfold_alphas(state: &GameState, now: Instant) -> Vec<u8> {
+    let visible = Duration::from_millis(BLINDFOLD_VISIBLE_MILLIS);
+    state
+        .placed_at
+        .iter()
+        .map(|placed_at| {
+            let age = placed_at.map_or(Duration::MAX, |placed_at| now - placed_at);
+            let fade = age.saturating_sub(visible).as_secs_f64() / Duration::from_millis(BLINDFOLD_FADE_MILLIS).as_secs_f64();
+            (255.0 * (1.0 - fade.min(1.0))) as u8
+        })
+        .collect()
+}
+
+/// Returns the color of the mark in a square with the given opacity, or None for an empty square.
+fn mark_color(square: Square, theme: &Theme, alpha: u8) -> Option<Color> {
+    let color = match square {
+        Square::X => theme.x,
+        Square::O => theme.o,
+        Square::Empty => return None,
+    };
+    Some(Color::RGBA(color.r, color.g, color.b, alpha))
+}
+
+/// Draws the squares of the board and the marks in them, each mark with the given opacity.
+fn draw_board(canvas: &mut WindowCanvas, squares: &[Square], alphas: &[u8], config: &Config, layout: &Layout, theme: &Theme) {
+    let rules = &config.rules;
+    let draw_glyph = if config.smooth { glyph::draw_glyph_smooth } else { glyph::draw_glyph };
+    if rules.hex {
+        // A glyph fits in the circle that touches the sides of the hexagon.
+        let cell = (hex::cell_radius(rules.size) * 3.0_f64.sqrt()) as u32;
+        for (index, square) in squares.iter().enumerate() {
+            let color = mark_color(*square, theme, alphas[index]);
+            let fill = if config.glyphs { None } else { color };
+            hex::draw_cell(canvas, rules.size, index, fill, theme.cell_outline);
+            if let (true, Some(color)) = (config.glyphs, color) {
+                draw_glyph(canvas, *square, square_center(rules, layout, index), cell, config.stroke, config.shadow, color);
+            }
+        }
+        return;
+    }
+
+    let size = rules.size;
+    let square_size = layout.square_size;
+    if config.guides {
+        draw_guides(canvas, rules, layout, theme);
+    }
+    for i in 0..size {
+        for j in 0..size {
+            let index = (j * size) + i;
+            let rect = layout.square_rect(index, config.gap);
+            canvas.set_draw_color(theme.cell_outline);
+            canvas.draw_rect(rect).unwrap();
+
+            let square = *get_square_flatten_index(squares, size, j, i);
+            if let Some(color) = mark_color(square, theme, alphas[index]) {
+                if config.glyphs {
+                    draw_glyph(canvas, square, layout.center(index), square_size, config.stroke, config.shadow, color);
+                } else {
+                    canvas.set_draw_color(color);
+                    canvas.fill_rect(get_inner_rect(rect)).unwrap();
+                }
             }
         }
     }
-    for lambda in DIAGONAL_LINE_LAMBDAS.iter() {
-        if let Some(winner) = line_winner(&squares, *lambda, 0) {
-            return Some(winner);
+
+    // The grid covers the two pixels where the outlines of neighbouring squares meet, so tiles with gaps between them
+    // have no grid.
+    if config.gap > 0 {
+        return;
+    }
+    canvas.set_draw_color(theme.grid);
+    let start = layout.origin;
+    let end = layout.origin + Point::new(layout.board_size() as i32 - 1, layout.board_size() as i32 - 1);
+    for k in 1..size as i32 {
+        let at = layout.origin + Point::new(square_size as i32 * k, square_size as i32 * k);
+        for shift in [-1, 0].iter() {
+            let (x, y) = (at.x() + shift, at.y() + shift);
+            canvas.draw_line(Point::new(x, start.y()), Point::new(x, end.y())).unwrap();
+            canvas.draw_line(Point::new(start.x(), y), Point::new(end.x(), y)).unwrap();
         }
     }
-    None
 }
 
-/// Returns the winner of the given line.
-/// This function operates in kind of a wonky way. Essentially it traverses the size of the board, and for each iteration,
-/// executes the provided function get_square() with the arguments: constant, i (the iteration number).
-fn line_winner(squares: &Vec<Square>, get_square: fn(usize, usize) -> (usize, usize), constant: usize) -> Option<Square> {
-    let start = get_square(constant, 0);
-    let line_square = get_square_flatten_index(squares, start.0, start.1);
-    if *line_square == Square::Empty {
-        return None;
+/// Draws faint guides across a board of squares, under where the marks go: the diagonals that win, and a dot in the
+/// center square when there is one.
+fn draw_guides(canvas: &mut WindowCanvas, rules: &Rules, layout: &Layout, theme: &Theme) {
+    let size = rules.size;
+    let start = layout.origin;
+    let end = layout.origin + Point::new(layout.board_size() as i32 - 1, layout.board_size() as i32 - 1);
+    canvas.set_draw_color(Color::RGBA(theme.grid.r, theme.grid.g, theme.grid.b, GUIDE_ALPHA));
+    if rules.win_condition == WinCondition::Line && rules.diagonal {
+        canvas.draw_line(start, end).unwrap();
     }
-    for i in 1..SQUARES as usize {
-        let square = get_square(constant, i);
-        if *get_square_flatten_index(squares, square.0, square.1) != *line_square {
-            return None;
+    if rules.win_condition == WinCondition::Line && rules.anti_diagonal {
+        canvas.draw_line(Point::new(start.x(), end.y()), Point::new(end.x(), start.y())).unwrap();
+    }
+    if size % 2 == 1 {
+        let dot = ((layout.square_size as f64 * GUIDE_DOT_FRACTION) as u32).max(2);
+        canvas.fill_rect(Rect::from_center(layout.center(size * size / 2), dot, dot)).unwrap();
+    }
+}
+
+/// Draws a glow in the given color just inside the edge of the hovered square, so it is clear which square a click
+/// would play in.
+fn draw_hover(canvas: &mut WindowCanvas, config: &Config, layout: &Layout, index: usize, color: Color) {
+    let rules = &config.rules;
+    if rules.hex {
+        hex::draw_cell(canvas, rules.size, index, None, Color::RGBA(color.r, color.g, color.b, HOVER_GLOW_ALPHAS[0]));
+        return;
+    }
+    let mut rect = get_inner_rect(layout.square_rect(index, config.gap));
+    for &alpha in HOVER_GLOW_ALPHAS.iter() {
+        canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, alpha));
+        canvas.draw_rect(rect).unwrap();
+        rect = get_inner_rect(rect);
+    }
+}
+
+/// Fills the inside of the square with the given index, leaving its outline.
+fn fill_cell(canvas: &mut WindowCanvas, config: &Config, layout: &Layout, index: usize, color: Color) {
+    let rules = &config.rules;
+    if rules.hex {
+        hex::draw_cell(canvas, rules.size, index, Some(color), Color::RGBA(0, 0, 0, 0));
+    } else {
+        fill_rectangle(canvas, get_inner_rect(layout.square_rect(index, config.gap)), color);
+    }
+}
+
+/// Highlights the square just played in a replay, fading it as the given progress goes from 0.0 to 1.0.
+fn draw_replay_change(canvas: &mut WindowCanvas, config: &Config, layout: &Layout, index: usize, progress: f64) {
+    fill_cell(canvas, config, layout, index, Color::RGBA(255, 255, 255, (REPLAY_HIGHLIGHT_ALPHA as f64 * (1.0 - progress)) as u8));
+}
+
+/// Tints each square by how many moves were counted in it, relative to the square with the most.
+fn draw_heatmap(canvas: &mut WindowCanvas, config: &Config, layout: &Layout, counts: &[u32]) {
+    let most = counts.iter().copied().max().unwrap_or(0);
+    if most == 0 {
+        return;
+    }
+    for (index, &count) in counts.iter().enumerate().filter(|&(_, &count)| count > 0) {
+        let alpha = (HEATMAP_COLOR.a as u32 * count / most) as u8;
+        fill_cell(canvas, config, layout, index, Color::RGBA(HEATMAP_COLOR.r, HEATMAP_COLOR.g, HEATMAP_COLOR.b, alpha));
+    }
+}
+
+/// Draws a dot in the middle of each square the player to move could play in, colored by what playing there leads to.
+/// Moves the search could not resolve are left without a dot.
+fn draw_eval(canvas: &mut WindowCanvas, rules: &Rules, layout: &Layout, evaluations: &[(usize, Outcome)], to_move: Square) {
+    let cell = if rules.hex { hex::cell_radius(rules.size) * 2.0 } else { layout.square_size as f64 };
+    let dot = ((cell * EVAL_DOT_FRACTION) as u32).max(2);
+    for &(index, outcome) in evaluations {
+        let color = match outcome {
+            Outcome::WinFor(winner) if winner == to_move => EVAL_COLORS[0],
+            Outcome::Draw => EVAL_COLORS[1],
+            Outcome::WinFor(_) => EVAL_COLORS[2],
+            Outcome::Ongoing => continue,
+        };
+        let center = square_center(rules, layout, index);
+        fill_rectangle(canvas, Rect::from_center(center, dot, dot), color);
+    }
+}
+
+/// Draws a three pixel wide arrow from one point to another, with its head at the second.
+fn draw_arrow(canvas: &mut WindowCanvas, from: Point, to: Point, color: Color) {
+    let (dx, dy) = ((to.x() - from.x()) as f64, (to.y() - from.y()) as f64);
+    let length = dx.hypot(dy);
+    if length < 1.0 {
+        return;
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    // The head's two sides sweep back from the tip, a little under thirty degrees either side of the shaft.
+    let (sin, cos) = 0.5_f64.sin_cos();
+    let back = |sin: f64| {
+        let x = -(ux * cos - uy * sin) * HINT_ARROW_HEAD;
+        let y = -(uy * cos + ux * sin) * HINT_ARROW_HEAD;
+        Point::new(to.x() + x.round() as i32, to.y() + y.round() as i32)
+    };
+    let (left, right) = (back(sin), back(-sin));
+    canvas.set_draw_color(color);
+    for offset in -1..=1 {
+        // The shaft is thickened across its direction, and the head along both axes.
+        let across = Point::new((-uy * offset as f64).round() as i32, (ux * offset as f64).round() as i32);
+        canvas.draw_line(from + across, to + across).unwrap();
+        for &side in [left, right].iter() {
+            canvas.draw_line(to + Point::new(offset, 0), side + Point::new(offset, 0)).unwrap();
+            canvas.draw_line(to + Point::new(0, offset), side + Point::new(0, offset)).unwrap();
         }
     }
-    Some(line_square.clone())
 }
 
-/// Returns a square value from the squares vector by treating it as a table.
-fn get_square_flatten_index(squares: &Vec<Square>, row: usize, col: usize) -> &Square {
-    &squares[(row * SQUARES as usize) + col]
+/// Faintly draws every winning line as a stroke through the centers of its squares.
+fn draw_winning_lines(canvas: &mut WindowCanvas, lines: &[Line], rules: &Rules, layout: &Layout) {
+    canvas.set_draw_color(Color::RGBA(255, 255, 0, 96));
+    for line in lines {
+        let first = square_center(rules, layout, line[0]);
+        let last = square_center(rules, layout, line[line.len() - 1]);
+        canvas.draw_line(first, last).unwrap();
+    }
 }
 
-/// Freezes the game in preparation of a new game.
-fn endgame(state: &mut GameState) {
-    state.freeze_until = Some(Instant::now() + Duration::from_secs(NEW_GAME_TIMEOUT))
+/// Returns how far through the win effects the game is, from 0.0 to 1.0, or None if they are not playing.
+fn win_effect_progress(state: &GameState, now: Instant) -> Option<(Square, f64)> {
+    let (winner, won_at) = state.won_at?;
+    let progress = (now - won_at).as_secs_f64() / Duration::from_millis(WIN_EFFECT_MILLIS).as_secs_f64();
+    if progress < 1.0 { Some((winner, progress)) } else { None }
 }
 
-fn main() {
-    let sdl = sdl2::init().unwrap();
-    let window = sdl.video().unwrap().window("Tic-Tac-Toe!", WINDOW_SIZE, WINDOW_SIZE)
-        .position_centered()
-        .build()
-        .unwrap();
+/// Returns the offset of the board while it shakes, which dies down as the effect plays out.
+fn shake_offset(progress: f64) -> (i32, i32) {
+    let amplitude = SHAKE_PIXELS * (1.0 - progress);
+    let angle = progress * std::f64::consts::PI * 12.0;
+    ((amplitude * angle.sin()).round() as i32, (amplitude * (angle * 1.3).cos()).round() as i32)
+}
 
-    let mut canvas = window.into_canvas().build().unwrap();
-    let mut event_pump = sdl.event_pump().unwrap();
+/// Something worked out for a position, kept so that it is only worked out again once the position changes.
+struct PositionCache<T> {
+    position: Option<(Vec<Square>, Square)>,
+    value: T,
+}
 
-    let screen_rect = Rect::new(0, 0, WINDOW_SIZE, WINDOW_SIZE);
-    let border_rect = Rect::new(BORDER_THICKNESS, BORDER_THICKNESS, WINDOW_SIZE - (BORDER_THICKNESS as u32 * 2), WINDOW_SIZE - (BORDER_THICKNESS as u32 * 2));
-    let playing_area_rect = Rect::new(
-        BORDER_THICKNESS * 2,
-        BORDER_THICKNESS * 2,
-        WINDOW_SIZE - (BORDER_THICKNESS as u32 * 4) - FILL_IN,
-        WINDOW_SIZE - (BORDER_THICKNESS as u32 * 4) - FILL_IN,
-    );
-
-    let mut state = GameState::default();
-
-    loop {
-        if state.freeze_until.is_some() {
-            if Instant::now() > state.freeze_until.unwrap() {
-                state.freeze_until = None;
-                state = GameState::default();
-            } else {
-                // We need to drain the event pump so that events from the
-                // frozen period are not picked up once input is re-enabled.
-                for _ in event_pump.poll_iter() { }
-            }
-        } else {
-            canvas.clear();
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                        return;
-                    },
-                    Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
-                        if let Some(square) = get_square_from_coords(x, y) {
-                            if state.squares[square] == Square::Empty {
-                                state.squares[square] = if state.turn { Square::X } else { Square::O };
-                                state.turn = !state.turn;
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
+impl<T: Default> PositionCache<T> {
+    fn new() -> Self {
+        Self { position: None, value: T::default() }
+    }
 
-            if let Some(winner) = get_winner(&state.squares) {
-                println!("{} wins!", if winner == Square::X { "Red" } else { "Blue" });
-                endgame(&mut state);
-            } else if !state.squares.iter().any(|s| *s == Square::Empty) {
-                println!("Draw!");
-                endgame(&mut state);
-            }
+    /// Returns the value for the board with the given player to move, working it out if the position has changed.
+    fn get(&mut self, squares: &[Square], to_move: Square, work_out: impl FnOnce() -> T) -> &T {
+        if !self.position.as_ref().is_some_and(|(cached, player)| cached == squares && *player == to_move) {
+            self.value = work_out();
+            self.position = Some((squares.to_vec(), to_move));
+        }
+        &self.value
+    }
+}
 
-            fill_rectangle(&mut canvas, screen_rect, Color::BLACK);
-            fill_rectangle(&mut canvas, border_rect, Color::WHITE);
-            fill_rectangle(&mut canvas, playing_area_rect, Color::BLACK);
+/// Counts the frames drawn over the last second.
+struct FpsCounter {
+    frames: u32,
+    since: Instant,
+    fps: u32,
+}
 
-            for i in 0..SQUARES as usize {
-                for j in 0..SQUARES as usize {
-                    let rect = Rect::new((PLAYING_AREA_OFFSET + (SQUARE_SIZE * i as u32)) as i32, (PLAYING_AREA_OFFSET + (SQUARE_SIZE * j as u32)) as i32, SQUARE_SIZE, SQUARE_SIZE);
-                    canvas.set_draw_color(Color::WHITE);
-                    canvas.draw_rect(rect).unwrap();
+impl FpsCounter {
+    fn new() -> Self {
+        Self { frames: 0, since: Instant::now(), fps: 0 }
+    }
 
-                    match get_square_flatten_index(&state.squares, j, i) {
-                        Square::X => {
-                            canvas.set_draw_color(Color::RED);
-                            canvas.fill_rect(get_inner_rect(rect)).unwrap();
-                        },
-                        Square::O => {
-                            canvas.set_draw_color(Color::BLUE);
-                            canvas.fill_rect(get_inner_rect(rect)).unwrap();
-                        },
-                        Square::Empty => (),
-                    };
-                }
-            }
-            canvas.present();
+    /// Records a frame, updating the frame rate once a second has passed.
+    fn tick(&mut self, now: Instant) {
+        self.frames += 1;
+        if now - self.since >= Duration::from_secs(1) {
+            self.fps = self.frames;
+            self.frames = 0;
+            self.since = now;
         }
     }
 }
+
+/// Draws the debug overlay, including the number of games left from the position, where None is too many to count.
+fn draw_debug_overlay(canvas: &mut WindowCanvas, state: &GameState, tree_size: Option<u64>, fps: u32, now: Instant) {
+    let freeze = match state.freeze_until {
+        Some(freeze_until) => format!("{:.2}s", freeze_until.saturating_duration_since(now).as_secs_f64()),
+        None => "-".to_string(),
+    };
+    let last_move = match state.history.last() {
+        Some(index) => index.to_string(),
+        None => "-".to_string(),
+    };
+    let lines = [
+        format!("turn: {}", if state.turn { "X" } else { "O" }),
+        format!("freeze: {}", freeze),
+        format!("last move: {}", last_move),
+        format!("moves: {}", state.history.len()),
+        format!("games left: {}", tree_size.map_or("huge".to_string(), |size| size.to_string())),
+        format!("hash: {:016x}", board_hash(&state.squares)),
+        format!("fps: {}", fps),
+    ];
+
+    let line_height = (font::GLYPH_HEIGHT + 2) * DEBUG_TEXT_SCALE;
+    let width = lines.iter().map(|line| font::text_width(line, DEBUG_TEXT_SCALE)).max().unwrap_or(0);
+    let x = PLAYING_AREA_OFFSET as i32 + 4;
+    let y = PLAYING_AREA_OFFSET as i32 + 4;
+    fill_rectangle(canvas, Rect::new(x - 2, y - 2, width + 4, line_height * lines.len() as u32 + 2), Color::RGBA(0, 0, 0, 192));
+    for (i, line) in lines.iter().enumerate() {
+        font::draw_text(canvas, line, x, y + (line_height * i as u32) as i32, DEBUG_TEXT_SCALE, Color::GREEN);
+    }
+}
+
+/// Saves the game to the configured file, reporting whether it worked.
+fn save_game(state: &GameState, config: &Config, toasts: &mut Toasts, now: Instant) {
+    match state.to_save(now).write(&config.save) {
+        Ok(()) => toasts.push(format!("saved to {}", config.save.display()), now),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            toasts.push("save failed", now);
+        },
+    }
+}
+
+/// Draws a faint note in the bottom border that the computer can force a win, without saying how.
+fn draw_lost_hint(canvas: &mut WindowCanvas) {
+    let text = "lost position";
+    let width = font::text_width(text, LOST_HINT_TEXT_SCALE) as i32;
+    let x = (WINDOW_SIZE - PLAYING_AREA_OFFSET) as i32 - width;
+    let y = WINDOW_SIZE as i32 - PLAYING_AREA_OFFSET as i32 + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * LOST_HINT_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, text, x, y, LOST_HINT_TEXT_SCALE, Color::RGBA(255, 255, 255, 96));
+}
+
+/// Draws the opening drill score in the middle of the top border.
+fn draw_drill_score(canvas: &mut WindowCanvas, best: u32, played: u32) {
+    let text = format!("best openings: {} of {}", best, played);
+    let width = font::text_width(&text, DRILL_TEXT_SCALE) as i32;
+    let x = (WINDOW_SIZE as i32 - width) / 2;
+    let y = BORDER_THICKNESS + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * DRILL_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, &text, x, y, DRILL_TEXT_SCALE, Color::WHITE);
+}
+
+/// Draws the level and the size of the board it is played on in the middle of the top border.
+fn draw_level(canvas: &mut WindowCanvas, level: u32, size: usize) {
+    let text = format!("level {}: {}x{}", level, size, size);
+    let width = font::text_width(&text, DRILL_TEXT_SCALE) as i32;
+    let x = (WINDOW_SIZE as i32 - width) / 2;
+    let y = BORDER_THICKNESS + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * DRILL_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, &text, x, y, DRILL_TEXT_SCALE, Color::WHITE);
+}
+
+/// Draws a note in the bottom border that the computer is thinking, with dots that count up while it does.
+fn draw_thinking(canvas: &mut WindowCanvas, elapsed: Duration) {
+    let dots = (elapsed.as_millis() / THINKING_DOT_MILLIS % 4) as usize;
+    let text = format!("thinking{}", ".".repeat(dots));
+    let x = PLAYING_AREA_OFFSET as i32;
+    let y = WINDOW_SIZE as i32 - PLAYING_AREA_OFFSET as i32 + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * THINKING_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, &text, x, y, THINKING_TEXT_SCALE, Color::RGBA(255, 255, 255, 160));
+}
+
+/// Draws the name, index, and mark of the square with the given index next to the mouse, kept inside the window.
+fn draw_tooltip(canvas: &mut WindowCanvas, index: usize, square: Square, size: usize, mouse: Point) {
+    let occupant = match square {
+        Square::X => "X",
+        Square::O => "O",
+        Square::Empty => "empty",
+    };
+    let text = format!("{} ({}): {}", square_name(index, size), index, occupant);
+    let (width, height) = (font::text_width(&text, TOOLTIP_TEXT_SCALE) + 4, font::GLYPH_HEIGHT * TOOLTIP_TEXT_SCALE + 4);
+    let x = (mouse.x() + TOOLTIP_OFFSET).min((WINDOW_SIZE - width) as i32);
+    let y = (mouse.y() + TOOLTIP_OFFSET).min((WINDOW_SIZE - height) as i32);
+    fill_rectangle(canvas, Rect::new(x, y, width, height), Color::RGBA(0, 0, 0, 192));
+    font::draw_text(canvas, &text, x + 2, y + 2, TOOLTIP_TEXT_SCALE, Color::WHITE);
+}
+
+/// Draws the screen where both players say they are ready, with who still has to and the key they press.
+fn draw_ready(canvas: &mut WindowCanvas, ready: [bool; 2], theme: &Theme, config: &Config) {
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+    let line_height = (font::GLYPH_HEIGHT + 3) * READY_TEXT_SCALE;
+    let top = (WINDOW_SIZE as i32 - (line_height * 2) as i32) / 2;
+    for (i, player) in [Square::X, Square::O].iter().enumerate() {
+        let text = if ready[i] {
+            format!("{}: ready", config.name(*player))
+        } else {
+            format!("{}: press {}", config.name(*player), READY_KEYS[i].name())
+        };
+        let x = (WINDOW_SIZE as i32 - font::text_width(&text, READY_TEXT_SCALE) as i32) / 2;
+        let color = if *player == Square::X { theme.x } else { theme.o };
+        font::draw_text(canvas, &text, x, top + (line_height * i as u32) as i32, READY_TEXT_SCALE, color);
+    }
+}
+
+/// Draws the current win streak in the middle of the bottom border, in the color of the player on it.
+fn draw_streak(canvas: &mut WindowCanvas, player: Square, streak: u32, theme: &Theme, config: &Config) {
+    let text = format!("{} streak: {}", config.name(player), streak);
+    let width = font::text_width(&text, STREAK_TEXT_SCALE) as i32;
+    let x = (WINDOW_SIZE as i32 - width) / 2;
+    let y = WINDOW_SIZE as i32 - PLAYING_AREA_OFFSET as i32 + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * STREAK_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, &text, x, y, STREAK_TEXT_SCALE, if player == Square::X { theme.x } else { theme.o });
+}
+
+/// Draws the pending draw offer in the bottom border, with how to answer it if it came from the other player.
+fn draw_draw_offer(canvas: &mut WindowCanvas, offered_by: Square, remote: Square, config: &Config) {
+    let text = if offered_by == remote {
+        format!("{} offers a draw: Y accepts, N declines", config.name(remote))
+    } else {
+        "draw offered".to_string()
+    };
+    draw_prompt(canvas, &text);
+}
+
+/// Draws the pending undo request in the bottom border, with how to answer it if it came from the other player.
+fn draw_undo_request(canvas: &mut WindowCanvas, requested_by: Square, remote: Square, config: &Config) {
+    let text = if requested_by == remote {
+        format!("{} asks to undo: Y accepts, N declines", config.name(remote))
+    } else {
+        "undo requested".to_string()
+    };
+    draw_prompt(canvas, &text);
+}
+
+/// Draws a question between the players in the middle of the bottom border.
+fn draw_prompt(canvas: &mut WindowCanvas, text: &str) {
+    let width = font::text_width(text, PROMPT_TEXT_SCALE) as i32;
+    let x = (WINDOW_SIZE as i32 - width) / 2;
+    let y = WINDOW_SIZE as i32 - PLAYING_AREA_OFFSET as i32 + (BORDER_THICKNESS - (font::GLYPH_HEIGHT * PROMPT_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, text, x, y, PROMPT_TEXT_SCALE, Color::WHITE);
+}
+
+/// Draws the coordinate being typed in the middle of the bottom border, with a mark where the next character goes.
+fn draw_entry(canvas: &mut WindowCanvas, entry: &str) {
+    draw_prompt(canvas, &format!("play at: {}_", entry));
+}
+
+/// Returns the letter or digit typed with the given key, if it is one.
+fn typed_char(keycode: Keycode) -> Option<char> {
+    let digit = match keycode {
+        Keycode::Kp0 => Some('0'),
+        Keycode::Kp1 => Some('1'),
+        Keycode::Kp2 => Some('2'),
+        Keycode::Kp3 => Some('3'),
+        Keycode::Kp4 => Some('4'),
+        Keycode::Kp5 => Some('5'),
+        Keycode::Kp6 => Some('6'),
+        Keycode::Kp7 => Some('7'),
+        Keycode::Kp8 => Some('8'),
+        Keycode::Kp9 => Some('9'),
+        _ => None,
+    };
+    // The keycodes of letter and digit keys are their lowercase characters.
+    digit.or_else(|| char::from_u32(keycode as i32 as u32).filter(|c| c.is_ascii_lowercase() || c.is_ascii_digit()))
+}
+
+/// Copies the game to the clipboard as its moves in notation, or as the board if it did not start from an empty one.
+fn copy_game(state: &GameState, rules: &Rules, clipboard: &ClipboardUtil, toasts: &mut Toasts, now: Instant) {
+    let text = if state.start.iter().all(|&square| square == Square::Empty) {
+        encode_game(&state.history, rules.size)
+    } else {
+        format_board(&state.squares)
+    };
+    if text.is_empty() {
+        toasts.push("nothing to copy", now);
+        return;
+    }
+    match clipboard.set_clipboard_text(&text) {
+        Ok(()) => toasts.push("copied to clipboard", now),
+        Err(e) => {
+            eprintln!("error: cannot copy to the clipboard: {}", e);
+            toasts.push("copy failed", now);
+        },
+    }
+}
+
+/// Draws the result of the last game across the middle of the window, on a dark band so it reads over the board.
+fn draw_result(canvas: &mut WindowCanvas, text: &str) {
+    let height = (font::GLYPH_HEIGHT * RESULT_TEXT_SCALE) as i32;
+    let y = (WINDOW_SIZE as i32 - height) / 2;
+    let band = Rect::new(0, y - RESULT_BAND_PADDING, WINDOW_SIZE, (height + RESULT_BAND_PADDING * 2) as u32);
+    fill_rectangle(canvas, band, Color::RGBA(0, 0, 0, 192));
+    let x = (WINDOW_SIZE as i32 - font::text_width(text, RESULT_TEXT_SCALE) as i32) / 2;
+    font::draw_text(canvas, text, x, y, RESULT_TEXT_SCALE, Color::WHITE);
+}
+
+/// Grays out the board of a drawn game, writing the word over it unless the result band already says so.
+fn draw_draw_overlay(canvas: &mut WindowCanvas, layout: &Layout, show_text: bool) {
+    let area = layout.area();
+    fill_rectangle(canvas, area, Color::RGBA(128, 128, 128, DRAW_WASH_ALPHA));
+    if show_text {
+        let text = "DRAW";
+        let x = area.center().x() - font::text_width(text, DRAW_TEXT_SCALE) as i32 / 2;
+        let y = area.center().y() - (font::GLYPH_HEIGHT * DRAW_TEXT_SCALE) as i32 / 2;
+        font::draw_text(canvas, text, x, y, DRAW_TEXT_SCALE, Color::WHITE);
+    }
+}
+
+/// Draws a bar above the playing area that shrinks as the time until the next game, out of the given delay, runs out.
+fn draw_countdown(canvas: &mut WindowCanvas, remaining: Duration, delay: Duration) {
+    let fraction = remaining.as_secs_f64() / delay.as_secs_f64();
+    let full_width = WINDOW_SIZE - (BORDER_THICKNESS as u32 * 2);
+    let width = (full_width as f64 * fraction) as u32;
+    if width == 0 {
+        return;
+    }
+    let y = (BORDER_THICKNESS - COUNTDOWN_BAR_HEIGHT as i32) / 2;
+    fill_rectangle(canvas, Rect::new(BORDER_THICKNESS, y, width, COUNTDOWN_BAR_HEIGHT), Color::WHITE);
+}
+
+/// How to get a working SDL2 on this platform, for when it cannot be started.
+#[cfg(target_os = "windows")]
+const SDL_INSTALL_HINT: &str = "The game needs SDL2: download the runtime from https://github.com/libsdl-org/SDL/releases \
+and put SDL2.dll next to the game.";
+#[cfg(target_os = "macos")]
+const SDL_INSTALL_HINT: &str = "The game needs SDL2: install it with 'brew install sdl2'.";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SDL_INSTALL_HINT: &str = "The game needs SDL2 and a display: install it with your package manager, like \
+'sudo apt install libsdl2-2.0-0' or 'sudo dnf install SDL2', and check that DISPLAY or WAYLAND_DISPLAY is set.";
+
+/// Exits with the reason SDL could not be started and how to fix it, rather than a panic.
+fn sdl_failed(error: &str) -> ! {
+    eprintln!("error: cannot start SDL: {}\n\n{}", error, SDL_INSTALL_HINT);
+    std::process::exit(1);
+}
+
+/// Opens the game window with a canvas drawn by the given renderer.
+fn open_window(video: &VideoSubsystem, renderer: Renderer) -> Result<WindowCanvas, String> {
+    // The window stays hidden until the first frame has been drawn, so that it never shows up empty.
+    let mut window = video.window("Tic-Tac-Toe!", WINDOW_SIZE, WINDOW_SIZE)
+        .position_centered()
+        .hidden()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // A missing or unreadable icon is not worth failing over, so just keep the default one.
+    if let Ok(icon) = Surface::load_bmp(ICON_PATH) {
+        window.set_icon(icon);
+    }
+
+    let builder = window.into_canvas();
+    let builder = match renderer {
+        Renderer::Default => builder,
+        Renderer::Software => builder.software(),
+        Renderer::Accelerated => builder.accelerated().present_vsync(),
+    };
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Something a player asked for with the keyboard, the mouse, or a controller, apart from the event that asked for it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    /// Close the game.
+    Quit,
+    /// Ask whether to close the game before doing it.
+    AskQuit,
+    /// Keep playing rather than close the game.
+    CancelQuit,
+    /// Replay the game that just ended, while the board is frozen.
+    Replay,
+    /// Show or hide the debug overlay.
+    ToggleDebug,
+    /// Show or hide the winning lines.
+    ToggleLines,
+    /// Swap to the other color palette.
+    SwapTheme,
+    /// Copy the game to the clipboard.
+    Copy,
+    /// Save the game to a file.
+    Save,
+    /// Open the pause menu.
+    OpenMenu,
+    /// Offer the other player a draw.
+    OfferDraw,
+    /// Accept or decline the draw the other player offered.
+    AnswerDraw(bool),
+    /// Take back the last move.
+    Undo,
+    /// Ask the other player to take back the last move.
+    RequestUndo,
+    /// Accept or refuse the other player's request to take back their move.
+    AnswerUndo(bool),
+    /// Turn the hint that the position is lost on or off.
+    ToggleLostHint,
+    /// Turn the move hints on or off.
+    ToggleHints,
+    /// Turn the fork hints on or off.
+    ToggleForks,
+    /// Turn the move evaluation on or off.
+    ToggleEval,
+    /// Turn the hover sound on or off.
+    ToggleHoverSound,
+    /// Turn the tooltip under the mouse on or off.
+    ToggleTooltip,
+    /// Move on to the next heatmap, or turn it off after the last.
+    CycleHeatmap,
+    /// Play in the square with the given index.
+    PlaceAt(usize),
+    /// Press the mouse on a square, to play there if it is released on the same square.
+    Press(usize),
+    /// Release the mouse over the given square, if any.
+    Release(Option<usize>),
+    /// Empty the square with the given index, in a sandbox game.
+    Clear(usize),
+    /// Start typing the coordinate of a square to play in.
+    StartEntry,
+    /// Add a letter or digit to the coordinate being typed.
+    Type(char),
+    /// Take the last character off the coordinate being typed.
+    Erase,
+    /// Play in the square whose coordinate was typed.
+    SubmitEntry,
+    /// Stop typing a coordinate without playing.
+    CancelEntry,
+    /// Pan the view by the given number of columns and rows.
+    Pan((i32, i32)),
+    /// Move the controller cursor by the given number of columns and rows.
+    MoveCursor((i32, i32)),
+    /// Start a new game.
+    Restart,
+}
+
+/// A scroll of the view toward a move made out of sight on a board too big for the playing area.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Follow {
+    from: (f64, f64),
+    to: (f64, f64),
+    started: Instant,
+}
+
+/// What the main loop should draw after a tick.
+enum Frame {
+    /// The board, with whatever is shown over it.
+    Board,
+    /// The match scoreboard in place of the board.
+    Scoreboard,
+    /// The screen where both players say they are ready, in place of the board.
+    Ready,
+    /// Nothing, since the player quit.
+    Quit,
+}
+
+/// Everything that carries over from one frame to the next, apart from the window itself.
+struct Session {
+    state: GameState,
+    rng: Rng,
+    /// The connection to the other player, if playing over the network and they are still there.
+    connection: Option<Connection>,
+    /// The program choosing the computer's moves, if there is one and it has not stopped.
+    engine: Option<Engine>,
+    /// The player on the other computer, if playing over the network.
+    remote: Option<Square>,
+    /// The clipboard games are copied to, if there is one.
+    clipboard: Option<ClipboardUtil>,
+    current_match: Option<Match>,
+    /// The time the scoreboard stops being shown, while it is.
+    scoreboard_until: Option<Instant>,
+    /// The point of a board too big for the playing area that is in the middle of it, as a share of the board.
+    view: (f64, f64),
+    /// The number of times the board has grown, counting from level 1, when it grows with each win.
+    level: u32,
+    /// The scroll toward a move made out of sight, while it plays.
+    follow: Option<Follow>,
+    /// The result of the last game and the time it stops being shown, while it is.
+    result: Option<(String, Instant)>,
+    stats: SessionStats,
+    achievements: Achievements,
+    menu: Option<Menu>,
+    toasts: Toasts,
+    show_debug: bool,
+    show_lines: bool,
+    show_lost: bool,
+    show_hint: bool,
+    show_forks: bool,
+    show_eval: bool,
+    hover_sound: bool,
+    show_tooltip: bool,
+    /// The computer's move being searched for, while it is.
+    thinking: Option<Thinking>,
+    /// The square picked out with a controller, once one has been used.
+    cursor: Option<usize>,
+    stick: Stick,
+    /// The number of best openings played in the drill, and the number of openings played.
+    drill_score: (u32, u32),
+    /// The moves the heatmap is showing, if it is shown.
+    heatmap: Option<Heatmap>,
+    /// The square the left mouse button was pressed on, when placing on release.
+    pressed_square: Option<usize>,
+    /// Whether the player pressed Escape during a game and is being asked whether to quit.
+    confirming_quit: bool,
+    /// Whether Red and Blue have said they are ready, until both have and the game starts.
+    ready: Option<[bool; 2]>,
+    /// The coordinate typed so far, such as "b1", once coordinate entry has been started with Tab.
+    entry: Option<String>,
+    /// The moves of the script still to be played, and the time the next one is.
+    script: VecDeque<usize>,
+    next_script_move: Instant,
+    theme_watcher: Option<ThemeWatcher>,
+    /// T flips between the configured theme and the built-in light one.
+    palettes: [Theme; 2],
+    palette: usize,
+}
+
+impl Session {
+    /// Returns a session starting with the given game at the given time, with nothing to reach outside the window:
+    /// no connection, engine, clipboard, or script, which are added by whoever needs them.
+    fn new(config: &Config, state: GameState, rng: Rng, theme: Theme, now: Instant) -> Self {
+        Self {
+            state,
+            rng,
+            connection: None,
+            engine: None,
+            remote: remote_player(config),
+            clipboard: None,
+            current_match: config.best_of.map(Match::new),
+            scoreboard_until: None,
+            view: (0.5, 0.5),
+            level: 1,
+            follow: None,
+            result: None,
+            stats: SessionStats::new(now, config.rules.squares()),
+            achievements: Achievements::default(),
+            menu: None,
+            toasts: Toasts::default(),
+            show_debug: false,
+            show_lines: false,
+            show_lost: config.show_lost,
+            show_hint: config.show_hint,
+            show_forks: config.show_forks,
+            show_eval: config.show_eval,
+            hover_sound: config.hover_sound,
+            show_tooltip: config.tooltip,
+            heatmap: None,
+            drill_score: (0, 0),
+            thinking: None,
+            cursor: None,
+            stick: Stick::default(),
+            pressed_square: None,
+            entry: None,
+            script: VecDeque::new(),
+            next_script_move: now,
+            confirming_quit: false,
+            ready: config.ready.then_some([false; 2]),
+            theme_watcher: config.theme.as_ref().map(|path| ThemeWatcher::new(path, now)),
+            palettes: [theme, Theme::light()],
+            palette: 0,
+        }
+    }
+
+    /// Works out what the player asked for with an event during a game, or returns None if the event asks for nothing.
+    /// Only the controller stick is tracked here; everything else is left to `apply`, so that any action can be
+    /// applied without an event behind it.
+    fn handle_event(&mut self, config: &Config, event: &Event) -> Option<Action> {
+        let networked = self.connection.is_some();
+        let layout = self.layout(config);
+        if self.confirming_quit {
+            return match *event {
+                Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Y), .. } => Some(Action::Quit),
+                Event::KeyDown { keycode: Some(Keycode::N | Keycode::Escape), .. } => Some(Action::CancelQuit),
+                _ => None,
+            };
+        }
+        // While a coordinate is being typed, letters and digits go into it rather than working as shortcuts.
+        if self.entry.is_some() {
+            if let Event::KeyDown { keycode: Some(keycode), .. } = *event {
+                match keycode {
+                    Keycode::Escape => return Some(Action::CancelEntry),
+                    Keycode::Backspace => return Some(Action::Erase),
+                    Keycode::Return | Keycode::KpEnter => return Some(Action::SubmitEntry),
+                    _ => {
+                        if let Some(c) = typed_char(keycode) {
+                            return Some(Action::Type(c));
+                        }
+                    },
+                }
+            }
+        }
+        match *event {
+            // Closing the window always quits, since that cannot be mistaken for anything else.
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. }
+                if config.confirm_quit && !self.state.history.is_empty() && self.state.freeze_until.is_none() =>
+            {
+                Some(Action::AskQuit)
+            },
+            Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => Some(Action::Quit),
+            Event::KeyDown { keycode: Some(Keycode::R), .. } => Some(Action::Replay),
+            Event::KeyDown { keycode: Some(Keycode::F3), .. } => Some(Action::ToggleDebug),
+            Event::KeyDown { keycode: Some(Keycode::F4), .. } => Some(Action::ToggleLines),
+            Event::KeyDown { keycode: Some(Keycode::T), .. } => Some(Action::SwapTheme),
+            Event::KeyDown { keycode: Some(Keycode::C), .. } => Some(Action::Copy),
+            Event::KeyDown { keycode: Some(Keycode::S), .. } => Some(Action::Save),
+            Event::KeyDown { keycode: Some(Keycode::M), .. } => Some(Action::OpenMenu),
+            Event::KeyDown { keycode: Some(Keycode::D), .. } if networked && self.state.draw_offer.is_none() && self.state.undo_request.is_none() => {
+                Some(Action::OfferDraw)
+            },
+            Event::KeyDown { keycode: Some(Keycode::Y), .. } if networked && self.state.draw_offer == self.remote => Some(Action::AnswerDraw(true)),
+            Event::KeyDown { keycode: Some(Keycode::N), .. } if networked && self.state.draw_offer == self.remote => Some(Action::AnswerDraw(false)),
+            Event::KeyDown { keycode: Some(Keycode::U), .. } if !networked => Some(Action::Undo),
+            Event::KeyDown { keycode: Some(Keycode::U), .. } => Some(Action::RequestUndo),
+            Event::KeyDown { keycode: Some(Keycode::Y), .. } if networked && self.state.undo_request == self.remote => Some(Action::AnswerUndo(true)),
+            Event::KeyDown { keycode: Some(Keycode::N), .. } if networked && self.state.undo_request == self.remote => Some(Action::AnswerUndo(false)),
+            Event::KeyDown { keycode: Some(Keycode::L), .. } if config.ai => Some(Action::ToggleLostHint),
+            Event::KeyDown { keycode: Some(Keycode::G), .. } => Some(Action::ToggleHints),
+            Event::KeyDown { keycode: Some(Keycode::F), .. } => Some(Action::ToggleForks),
+            Event::KeyDown { keycode: Some(Keycode::E), .. } => Some(Action::ToggleEval),
+            Event::KeyDown { keycode: Some(Keycode::A), .. } => Some(Action::ToggleHoverSound),
+            Event::KeyDown { keycode: Some(Keycode::F5), .. } => Some(Action::ToggleTooltip),
+            Event::KeyDown { keycode: Some(Keycode::H), .. } => Some(Action::CycleHeatmap),
+            Event::KeyDown { keycode: Some(Keycode::Tab), .. } => Some(Action::StartEntry),
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let square = square_at(config, &layout, x, y)?;
+                Some(if config.place_on_release { Action::Press(square) } else { Action::PlaceAt(square) })
+            },
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } if config.place_on_release => {
+                Some(Action::Release(square_at(config, &layout, x, y)))
+            },
+            Event::MouseButtonDown { mouse_btn: MouseButton::Right, x, y, .. } if config.sandbox => {
+                square_at(config, &layout, x, y).map(Action::Clear)
+            },
+            Event::KeyDown { keycode: Some(keycode @ (Keycode::Left | Keycode::Right | Keycode::Up | Keycode::Down)), .. } => {
+                Some(Action::Pan(match keycode {
+                    Keycode::Left => (-1, 0),
+                    Keycode::Right => (1, 0),
+                    Keycode::Up => (0, -1),
+                    _ => (0, 1),
+                }))
+            },
+            // The first press shows the cursor in the middle of the board rather than playing there.
+            Event::ControllerButtonDown { button: Button::A, .. } => {
+                Some(self.cursor.map_or(Action::MoveCursor((0, 0)), Action::PlaceAt))
+            },
+            Event::ControllerButtonDown { button: Button::B, .. } if !networked => Some(Action::Undo),
+            Event::ControllerButtonDown { button: Button::Start, .. } => Some(Action::Restart),
+            Event::ControllerButtonDown { button, .. } => dpad_step(button).map(Action::MoveCursor),
+            Event::ControllerAxisMotion { axis, value, .. } => self.stick.step(axis, value).map(Action::MoveCursor),
+            _ => None,
+        }
+    }
+
+    /// Does what the player asked for. Quitting is left to the caller, since it ends the loop rather than the game.
+    fn apply(&mut self, config: &Config, action: Action, now: Instant) {
+        match action {
+            Action::Quit => {},
+            Action::AskQuit => self.confirming_quit = true,
+            Action::CancelQuit => self.confirming_quit = false,
+            Action::Replay => {
+                if let Some(freeze_until) = self.state.freeze_until {
+                    self.state.replay = Some(Replay::new(self.state.start.clone(), self.state.first, self.state.history.clone(), freeze_until - now, now));
+                }
+            },
+            Action::ToggleDebug => self.show_debug = !self.show_debug,
+            Action::ToggleLines => self.show_lines = !self.show_lines,
+            Action::SwapTheme => self.palette = 1 - self.palette,
+            Action::Copy => {
+                if let Some(clipboard) = &self.clipboard {
+                    copy_game(&self.state, &config.rules, clipboard, &mut self.toasts, now);
+                }
+            },
+            Action::Save => save_game(&self.state, config, &mut self.toasts, now),
+            Action::OpenMenu => self.menu = Some(Menu::default()),
+            Action::OfferDraw => {
+                self.state.draw_offer = self.remote.map(Square::opponent);
+                send_message(&mut self.connection, &mut self.toasts, Message::OfferDraw, now);
+            },
+            Action::AnswerDraw(true) => {
+                self.state.draw_agreed = true;
+                send_message(&mut self.connection, &mut self.toasts, Message::AcceptDraw, now);
+            },
+            Action::AnswerDraw(false) => {
+                self.state.draw_offer = None;
+                send_message(&mut self.connection, &mut self.toasts, Message::DeclineDraw, now);
+            },
+            // Moves cannot be taken back over the network without asking, since the other player would not know.
+            Action::Undo => undo(&mut self.state, config),
+            Action::RequestUndo => {
+                let local = self.remote.map(Square::opponent);
+                if self.state.history.is_empty() {
+                    self.toasts.push("nothing to undo", now);
+                } else if local.is_some_and(|local| can_request_undo(&self.state, local)) {
+                    self.state.undo_request = local;
+                    send_message(&mut self.connection, &mut self.toasts, Message::RequestUndo, now);
+                }
+            },
+            Action::AnswerUndo(accepted) => {
+                self.state.undo_request = None;
+                if accepted {
+                    take_back(&mut self.state);
+                }
+                send_message(&mut self.connection, &mut self.toasts, if accepted { Message::AcceptUndo } else { Message::DeclineUndo }, now);
+            },
+            Action::ToggleLostHint => {
+                self.show_lost = !self.show_lost;
+                self.toasts.push(if self.show_lost { "lost position hint on" } else { "lost position hint off" }, now);
+            },
+            Action::ToggleHints => {
+                self.show_hint = !self.show_hint;
+                self.toasts.push(if self.show_hint { "move hints on" } else { "move hints off" }, now);
+            },
+            Action::ToggleForks => {
+                self.show_forks = !self.show_forks;
+                self.toasts.push(if self.show_forks { "fork hints on" } else { "fork hints off" }, now);
+            },
+            Action::ToggleEval => {
+                self.show_eval = !self.show_eval;
+                self.toasts.push(if self.show_eval { "move evaluation on" } else { "move evaluation off" }, now);
+            },
+            Action::ToggleHoverSound => {
+                self.hover_sound = !self.hover_sound;
+                self.toasts.push(if self.hover_sound { "hover sound on" } else { "hover sound off" }, now);
+            },
+            Action::ToggleTooltip => self.show_tooltip = !self.show_tooltip,
+            Action::CycleHeatmap => {
+                let (heatmap, text) = match self.heatmap {
+                    None => (Some(Heatmap::Played), "heatmap of moves played"),
+                    Some(Heatmap::Played) => (Some(Heatmap::Winning), "heatmap of winning moves"),
+                    Some(Heatmap::Winning) => (None, "heatmap off"),
+                };
+                self.heatmap = heatmap;
+                self.toasts.push(text, now);
+            },
+            Action::PlaceAt(square) => {
+                if click_square(&mut self.state, &mut self.toasts, config, square, now) {
+                    send_message(&mut self.connection, &mut self.toasts, Message::Move(square), now);
+                }
+            },
+            Action::Press(square) => self.pressed_square = Some(square),
+            // Dragging off the pressed square before releasing cancels the click.
+            Action::Release(square) => {
+                let pressed = self.pressed_square.take();
+                if let Some(square) = square.filter(|&square| Some(square) == pressed) {
+                    self.apply(config, Action::PlaceAt(square), now);
+                }
+            },
+            Action::Clear(square) => {
+                self.state.squares[square] = Square::Empty;
+                self.state.board_winner = None;
+                self.state.history.retain(|&played| played != square);
+                self.state.placed_at[square] = None;
+            },
+            Action::StartEntry => self.entry = Some(String::new()),
+            // A coordinate is a column letter followed by a row number, so anything else is ignored where it is typed.
+            Action::Type(c) => {
+                if let Some(entry) = &mut self.entry {
+                    let fits = if entry.is_empty() { c.is_ascii_alphabetic() } else { c.is_ascii_digit() && entry.len() <= 2 };
+                    if fits {
+                        entry.push(c);
+                    }
+                }
+            },
+            Action::Erase => {
+                if let Some(entry) = &mut self.entry {
+                    entry.pop();
+                }
+            },
+            Action::SubmitEntry => {
+                if let Some(entry) = self.entry.take() {
+                    match parse_square(&entry, config.rules.size) {
+                        Some(square) => self.apply(config, Action::PlaceAt(square), now),
+                        None => self.toasts.push("no such square", now),
+                    }
+                }
+            },
+            Action::CancelEntry => self.entry = None,
+            // Panning by hand takes over from any scroll toward the last move.
+            Action::Pan((cols, rows)) => {
+                self.follow = None;
+                self.view = self.layout(config).pan(self.view, cols, rows);
+            },
+            Action::MoveCursor(step) => self.move_cursor(config, step),
+            Action::Restart => {
+                self.ready = config.ready.then_some([false; 2]);
+                self.state = GameState::new(config.rules.squares(), config.clock, next_first(config.start_order, self.state.first, &mut self.rng));
+            },
+        }
+    }
+
+    /// Returns whether the action plays or takes back a move, restarts the game, or answers for a player, which a
+    /// script being played does not leave room for.
+    fn interrupts_script(action: &Action) -> bool {
+        matches!(
+            action,
+            Action::PlaceAt(_)
+                | Action::Press(_)
+                | Action::Release(_)
+                | Action::Clear(_)
+                | Action::SubmitEntry
+                | Action::Undo
+                | Action::RequestUndo
+                | Action::AnswerUndo(_)
+                | Action::OfferDraw
+                | Action::AnswerDraw(_)
+                | Action::Restart
+        )
+    }
+
+    /// Makes the board one size bigger for the next game, along with the win length if it is the whole board.
+    fn grow(&mut self, config: &mut Config, lines: &mut Vec<Line>, now: Instant) {
+        let rules = &mut config.rules;
+        if rules.win_length == rules.size {
+            rules.win_length += 1;
+        }
+        rules.size += 1;
+        *lines = winning_lines(rules);
+        self.level += 1;
+        // Anything that points at squares of the old board would point at the wrong ones on the new one.
+        self.stats.clear_heat(rules.squares());
+        self.cursor = None;
+        self.view = (0.5, 0.5);
+        self.follow = None;
+        println!("Level {}: the board grows to {}x{}", self.level, rules.size, rules.size);
+        self.toasts.push("the board grows", now);
+    }
+
+    /// Returns where the squares of the board are drawn, following the view on a board too big for the playing area.
+    fn layout(&self, config: &Config) -> Layout {
+        Layout::new(config.rules.size, config.min_square, self.view)
+    }
+
+    /// Returns the square under the mouse at the given coordinates, which is none while the menu is open or the game is
+    /// frozen, since a click would not play there.
+    fn hovered(&self, config: &Config, x: i32, y: i32) -> Option<usize> {
+        if self.menu.is_none() && self.state.freeze_until.is_none() { square_at(config, &self.layout(config), x, y) } else { None }
+    }
+
+    /// Moves the controller cursor by the given number of columns and rows, starting it in the middle of the board.
+    fn move_cursor(&mut self, config: &Config, step: (i32, i32)) {
+        let from = self.cursor.unwrap_or(config.rules.squares() / 2);
+        self.cursor = Some(move_cursor(config.rules.size, from, step));
+    }
+
+    /// Starts the next game once the last one is over, growing the board first if it grows after a win.
+    fn next_game(&mut self, config: &mut Config, lines: &mut Vec<Line>, now: Instant) {
+        if let (Some(max_size), Some(Some(_))) = (config.grow, self.state.ended) {
+            if config.rules.size < max_size {
+                self.grow(config, lines, now);
+            }
+        }
+        self.state = GameState::new(config.rules.squares(), config.clock, next_first(config.start_order, self.state.first, &mut self.rng));
+        // A game can end with moves of the script left, such as by running out of time, and they are only for it.
+        self.script.clear();
+        self.ready = config.ready.then_some([false; 2]);
+        // A held result stays up until the next game starts, and no longer.
+        if config.hold_result {
+            self.result = None;
+        }
+        if self.current_match.is_some() {
+            self.scoreboard_until = Some(now + Duration::from_secs(SCOREBOARD_TIMEOUT));
+        }
+    }
+
+    /// Advances the game to the given time, handling the given events, and returns what should be drawn.
+    /// Nothing here reads the real clock or event pump, so a game can be stepped through with made-up input.
+    /// The board can grow between games, so the config and the winning lines can change here.
+    fn tick(&mut self, config: &mut Config, lines: &mut Vec<Line>, events: Vec<Event>, now: Instant) -> Frame {
+        // Clocks only run while a game is being played, so menus, scoreboards, and freezes do not drain them.
+        let playing = self.menu.is_none() && self.scoreboard_until.is_none() && self.ready.is_none() && self.state.freeze_until.is_none();
+        let to_move = self.state.to_move();
+        if let Some(clock) = &mut self.state.clock {
+            if playing { clock.run(to_move, now) } else { clock.stop() }
+        }
+
+        if let Some(Follow { from, to, started }) = self.follow {
+            // The scroll eases in and out, so that the board does not jump into motion.
+            let progress = ((now - started).as_secs_f64() / Duration::from_millis(FOLLOW_MILLIS).as_secs_f64()).min(1.0);
+            let eased = progress * progress * (3.0 - 2.0 * progress);
+            self.view = (from.0 + (to.0 - from.0) * eased, from.1 + (to.1 - from.1) * eased);
+            if progress >= 1.0 {
+                self.follow = None;
+            }
+        }
+
+        if let Some(open_menu) = &mut self.menu {
+            let mut action = None;
+            for event in events {
+                if let Event::Quit { .. } = event {
+                    return Frame::Quit;
+                }
+                action = action.or_else(|| open_menu.handle(&event));
+            }
+            match action {
+                Some(MenuAction::Resume) => self.menu = None,
+                Some(MenuAction::NewGame) => {
+                    self.script.clear();
+                    self.ready = config.ready.then_some([false; 2]);
+                    self.state = GameState::new(config.rules.squares(), config.clock, next_first(config.start_order, self.state.first, &mut self.rng));
+                    self.menu = None;
+                },
+                Some(MenuAction::Save) => {
+                    save_game(&self.state, config, &mut self.toasts, now);
+                    self.menu = None;
+                },
+                Some(MenuAction::Quit) => return Frame::Quit,
+                None => {},
+            }
+        } else if let (Some(until), Some(current_match)) = (self.scoreboard_until, &mut self.current_match) {
+            let mut skipped = false;
+            for event in events {
+                match event {
+                    Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => return Frame::Quit,
+                    Event::KeyDown { .. } | Event::MouseButtonDown { .. } => skipped = true,
+                    _ => {}
+                }
+            }
+            if skipped || now > until {
+                self.scoreboard_until = None;
+                current_match.reset_if_over();
+            } else {
+                return Frame::Scoreboard;
+            }
+        } else if let Some(ready) = &mut self.ready {
+            for event in events {
+                match event {
+                    Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => return Frame::Quit,
+                    Event::KeyDown { keycode: Some(keycode), .. } => {
+                        if let Some(player) = READY_KEYS.iter().position(|&key| key == keycode) {
+                            ready[player] = true;
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            if *ready != [true; 2] {
+                return Frame::Ready;
+            }
+            self.ready = None;
+        } else if let Some(freeze_until) = self.state.freeze_until {
+            if let Some(replay) = &mut self.state.replay {
+                if !replay.step(now) {
+                    self.state.freeze_until = Some(now + replay.remaining_freeze);
+                    self.state.replay = None;
+                }
+            } else if now > freeze_until && !config.hold_result {
+                self.next_game(config, lines, now);
+            } else {
+                // Any other events from the frozen period are dropped, so that they are not picked up once input is
+                // re-enabled. Only quitting and the replay, debug, theme, copy, save, and menu keys are handled here,
+                // and any other key or click starts the next game when the result is held.
+                let mut next = false;
+                for event in events {
+                    match self.handle_event(config, &event) {
+                        Some(Action::Quit) => return Frame::Quit,
+                        Some(
+                            action @ (Action::CancelQuit
+                            | Action::Replay
+                            | Action::ToggleDebug
+                            | Action::ToggleLines
+                            | Action::SwapTheme
+                            | Action::Copy
+                            | Action::Save
+                            | Action::OpenMenu),
+                        ) => self.apply(config, action, now),
+                        _ => next |= matches!(event, Event::KeyDown { .. } | Event::MouseButtonDown { .. }),
+                    }
+                }
+                if next && config.hold_result && !self.confirming_quit {
+                    self.next_game(config, lines, now);
+                }
+            }
+        } else {
+            let moves_before = self.state.history.len();
+            for event in events {
+                match self.handle_event(config, &event) {
+                    Some(Action::Quit) => return Frame::Quit,
+                    Some(action) if !self.script.is_empty() && Self::interrupts_script(&action) => {},
+                    Some(action) => self.apply(config, action, now),
+                    None => {},
+                }
+            }
+            // The script plays both sides, one move at a time, before anyone else can.
+            if now >= self.next_script_move {
+                if let Some(square) = self.script.pop_front() {
+                    place(&mut self.state, square, now);
+                    self.next_script_move = now + Duration::from_millis(SCRIPT_STEP_MILLIS);
+                }
+            }
+
+            // Openings are judged before the computer can answer them, and the board is cleared for the next one.
+            if config.drill && self.state.history.len() == 1 {
+                let rules = &config.rules;
+                let best = classify_position(&self.state.start, self.state.first, rules);
+                let played = classify_position(&self.state.squares, self.state.to_move(), rules);
+                let (best_count, count) = &mut self.drill_score;
+                *count += 1;
+                if played == best {
+                    *best_count += 1;
+                    self.toasts.push("one of the best openings", now);
+                } else {
+                    self.toasts.push(match played {
+                        Outcome::WinFor(winner) if winner != self.state.first => "that opening loses",
+                        Outcome::Draw => "that opening only draws",
+                        // Big boards cannot be searched to the end, so their openings cannot be compared.
+                        _ => "that opening is too deep to judge",
+                    }, now);
+                }
+                self.state = GameState::new(rules.squares(), config.clock, next_first(config.start_order, self.state.first, &mut self.rng));
+            }
+
+            // The result is the winner, or None for a draw. Sandbox games never end, so the board can be edited freely.
+            // Messages are only read during a game, so any sent while this side is frozen wait for the next one.
+            if let (Some(open), Some(remote)) = (&mut self.connection, self.remote) {
+                match open.receive() {
+                    Ok(messages) => {
+                        let replies: Vec<_> = messages
+                            .into_iter()
+                            .filter_map(|message| receive_message(&mut self.state, &mut self.toasts, message, remote, lines, now))
+                            .collect();
+                        for reply in replies {
+                            send_message(&mut self.connection, &mut self.toasts, reply, now);
+                        }
+                    },
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        self.toasts.push("opponent disconnected", now);
+                        self.connection = None;
+                    },
+                }
+            }
+
+            // The computer starts thinking as soon as it is its turn, unless the game is already over, and plays once it
+            // has found its move. A search for a board that was undone or restarted since is given up on.
+            if self.thinking.as_ref().is_some_and(|thinking| thinking.squares != self.state.squares) {
+                self.thinking = None;
+            }
+            if config.ai && self.state.to_move() == AI_PLAYER && self.script.is_empty() && !game_over(&self.state.squares, lines) {
+                if self.thinking.is_none() {
+                    let squares = &self.state.squares;
+                    let asked = self.engine.as_mut().map(|engine| engine.ask(squares));
+                    self.thinking = Some(match (blunder(squares, lines, config.blunder, &mut self.rng), asked) {
+                        (Some(square), _) => Thinking::decided(squares, square, now),
+                        (None, Some(Ok(receiver))) => Thinking::from_engine(squares, receiver, now),
+                        (None, Some(Err(e))) => {
+                            eprintln!("error: the engine has stopped, so the computer plays for it: {}", e);
+                            self.toasts.push("engine stopped", now);
+                            self.engine = None;
+                            Thinking::start(squares, &config.rules, config.think, now)
+                        },
+                        (None, None) => Thinking::start(squares, &config.rules, config.think, now),
+                    });
+                }
+                let answer = self.thinking.as_ref().map(|thinking| thinking.receiver.try_recv());
+                match answer {
+                    Some(Ok(found)) => {
+                        self.thinking = None;
+                        if let Some(square) = found {
+                            if config.explain {
+                                let reason = move_reason(&self.state.squares, lines, AI_PLAYER, square);
+                                let text = explanation(reason);
+                                println!("{} plays {}: {}", config.name(AI_PLAYER), square_name(square, config.rules.size), text);
+                                self.toasts.push(text, now);
+                            }
+                            place(&mut self.state, square, now);
+                        }
+                    },
+                    // An engine that gives no move has the computer's own search play this one for it.
+                    Some(Err(TryRecvError::Disconnected)) if self.thinking.as_ref().is_some_and(|thinking| thinking.engine) => {
+                        self.toasts.push("engine gave no move", now);
+                        self.thinking = Some(Thinking::start(&self.state.squares, &config.rules, config.think, now));
+                    },
+                    Some(Err(TryRecvError::Disconnected)) => self.thinking = None,
+                    Some(Err(TryRecvError::Empty)) | None => {},
+                }
+            }
+            // Whether the player is lost is only worked out while the hint is shown, on another thread since it can take
+            // as long as the computer's own search.
+            if config.ai && self.show_lost {
+                if self.state.lost_check.as_ref().is_none_or(|check| check.squares != self.state.squares) {
+                    self.state.lost = false;
+                    self.state.lost_check = Some(Thinking::lost(&self.state.squares, self.state.to_move(), &config.rules, now));
+                }
+                if let Some(Ok(lost)) = self.state.lost_check.as_ref().map(|check| check.receiver.try_recv()) {
+                    self.state.lost = lost;
+                }
+            }
+            // A move made out of sight on a panned board, such as by the computer or the other player, is scrolled to.
+            let layout = self.layout(config);
+            if let Some((square, _)) = self.state.last_placement.filter(|_| self.state.history.len() != moves_before && !config.rules.hex) {
+                if layout.is_panned() && !layout.shows(square) {
+                    self.follow = Some(Follow { from: self.view, to: layout.view_of(square), started: now });
+                }
+            }
+
+            // A player who has run out of time loses, unless the game was already decided on the board.
+            let to_move = self.state.to_move();
+            let winner = self.state.winner(lines);
+            let out_of_time = winner.is_none() && self.state.clock.as_ref().is_some_and(|clock| clock.flagged(to_move));
+            let capped = config.max_moves.is_some_and(|max_moves| self.state.history.len() >= max_moves as usize);
+            let result = match winner {
+                _ if config.sandbox => None,
+                Some(winner) => Some(Some(winner)),
+                None if self.state.draw_agreed => Some(None),
+                None if capped => Some(None),
+                None if !self.state.squares.contains(&Square::Empty) => Some(None),
+                None if out_of_time => Some(Some(to_move.opponent())),
+                None => None,
+            };
+            // A game drawn on the board goes to the tie-breaker, if there is one, but an agreed draw stays a draw.
+            let tie_winner = match (result, config.tie_breaker) {
+                (Some(None), Some(tie_breaker)) if !self.state.draw_agreed => tie_breaker.winner(&self.state.squares, config.rules.size),
+                _ => None,
+            };
+            let result = if tie_winner.is_some() { Some(tie_winner) } else { result };
+            if let Some(winner) = result {
+                let message = match winner {
+                    Some(winner) if out_of_time => {
+                        format!("{} ran out of time. {} wins!", config.name(to_move), config.name(winner))
+                    },
+                    Some(winner) if tie_winner.is_some() => format!("Draw! {} wins the tie-breaker!", config.name(winner)),
+                    Some(winner) => format!("{} wins!", config.name(winner)),
+                    None if self.state.draw_agreed => "Draw agreed!".to_string(),
+                    None if capped => "Draw by move limit!".to_string(),
+                    None => "Draw!".to_string(),
+                };
+                match self.state.started_at {
+                    Some(started_at) => {
+                        self.stats.record_game_time(now - started_at);
+                        let average = self.stats.average_game_time().unwrap_or_default();
+                        println!("{} ({:.1}s, average {:.1}s)", message, (now - started_at).as_secs_f64(), average.as_secs_f64());
+                    },
+                    None => println!("{}", message),
+                }
+                endgame(&mut self.state, config.restart, now);
+                // A coordinate half typed when the game ends would otherwise swallow the keys used while it is frozen.
+                self.entry = None;
+                self.result = Some((message, now + config.result));
+                self.stats.record_result(winner);
+                self.stats.record_moves(&self.state.history, winner.is_some() && !out_of_time && tie_winner.is_none());
+                if let Some(path) = &config.history {
+                    let state = &self.state;
+                    if let Err(e) = history::append(path, &state.start, state.first, &state.history, config.rules.size, winner) {
+                        eprintln!("error: cannot write to history '{}': {}", path.display(), e);
+                        self.toasts.push("history not written", now);
+                    }
+                }
+                self.state.won_at = winner.map(|winner| (winner, now));
+                self.state.ended = Some(winner);
+                if let Some(current_match) = &mut self.current_match {
+                    current_match.record(winner);
+                }
+                // Only the players at this computer earn achievements, not the computer or the other player.
+                let remote = if config.ai { Some(AI_PLAYER) } else { self.remote };
+                if let Some(winner) = winner.filter(|&winner| remote != Some(winner)) {
+                    let current_match = self.current_match.as_ref().filter(|current_match| current_match.winner() == Some(winner));
+                    let game = WonGame {
+                        start: &self.state.start,
+                        first: self.state.first,
+                        moves: &self.state.history,
+                        lines,
+                        winner,
+                        duration: self.state.started_at.map(|started_at| now - started_at),
+                        match_won: current_match.map(|current_match| current_match.wins(winner.opponent())),
+                    };
+                    for name in self.achievements.check(&game) {
+                        self.toasts.push(format!("achievement: {}", name), now);
+                    }
+                }
+            }
+        }
+
+        // A theme that fails to reload keeps the previous colors, so a half-finished edit does not break the game.
+        match self.theme_watcher.as_mut().and_then(|watcher| watcher.poll(now)) {
+            Some(Ok(reloaded)) => {
+                self.palettes[0] = reloaded;
+                self.toasts.push("theme reloaded", now);
+            },
+            Some(Err(e)) => {
+                eprintln!("error: {}", e);
+                self.toasts.push(e, now);
+            },
+            None => {},
+        }
+
+        Frame::Board
+    }
+}
+
+/// Prints every winning line for the board, one per row, as the indices of its squares and then their names.
+fn dump_lines(rules: &Rules) {
+    let lines = winning_lines(rules);
+    let noun = if lines.len() == 1 { "line" } else { "lines" };
+    println!("{} winning {} on the {}x{} board:", lines.len(), noun, rules.size, rules.size);
+    for line in &lines {
+        let indices: Vec<String> = line.iter().map(|index| index.to_string()).collect();
+        let names: Vec<String> = line.iter().map(|&index| square_name(index, rules.size)).collect();
+        println!("{}  ({})", indices.join(" "), names.join(" "));
+    }
+}
+
+fn main() {
+    let mut config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        },
+    };
+    // The seed is settled once, so that everything random in the session and a dumped config all use the same one.
+    config.seed = Some(config.seed());
+    if let Some(path) = &config.dump_config {
+        if let Err(e) = std::fs::write(path, config.to_toml()) {
+            eprintln!("error: cannot write config file '{}': {}", path.display(), e);
+            std::process::exit(1);
+        }
+        println!("Settings written to {}", path.display());
+        return;
+    }
+    if config.dump_lines {
+        dump_lines(&config.rules);
+        return;
+    }
+    if let Some(games) = config.research {
+        research::run(games, config.seed(), &config.rules);
+        return;
+    }
+    if let Some(games) = config.tournament {
+        let (red, blue) = (config.red, config.blue);
+        if let Err(e) = tournament::run(games, config.seed(), &config.rules, red, blue, config.max_moves, &config.report) {
+            eprintln!("error: cannot write report '{}': {}", config.report.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut lines = winning_lines(&config.rules);
+    let theme = match &config.theme {
+        Some(path) => Theme::load(path).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }),
+        None => Theme::default(),
+    };
+
+    // The other player is reached before the window opens, since hosting waits for them to connect.
+    let connection = config.network.as_ref().map(|network| {
+        let opened = match network {
+            Network::Host(port) => Connection::host(*port),
+            Network::Connect(address) => Connection::connect(address),
+        };
+        opened.unwrap_or_else(|e| {
+            eprintln!("error: cannot reach the other player: {}", e);
+            std::process::exit(2);
+        })
+    });
+    let engine = config.engine.as_ref().map(|command| {
+        Engine::spawn(command).unwrap_or_else(|e| {
+            eprintln!("error: cannot start the engine '{}': {}", command, e);
+            std::process::exit(2);
+        })
+    });
+
+    sdl2::hint::set("SDL_APP_NAME", APP_NAME);
+    sdl2::hint::set("SDL_VIDEO_X11_WMCLASS", "tic-tac-toe");
+
+    let sdl = sdl2::init().unwrap_or_else(|e| sdl_failed(&e));
+    let video = sdl.video().unwrap_or_else(|e| sdl_failed(&e));
+    let clipboard = video.clipboard();
+    // Not every driver offers every renderer (there may be no GPU, or no software fallback), so one that
+    // was asked for but is missing gives way to whichever SDL picks.
+    let mut canvas = open_window(&video, config.renderer).unwrap_or_else(|e| {
+        eprintln!("error: cannot use the {:?} renderer, using the default one instead: {}", config.renderer, e);
+        open_window(&video, Renderer::Default).unwrap()
+    });
+    println!("Drawing with the {} renderer", canvas.info().name);
+    if let Some(path) = &config.font {
+        if let Err(e) = font::load(path) {
+            eprintln!("error: cannot load font '{}', using the built-in one instead: {}", path.display(), e);
+        }
+    }
+    canvas.set_blend_mode(BlendMode::Blend);
+    let mut event_pump = sdl.event_pump().unwrap();
+    // Controllers are optional too, so the game plays on with the mouse and keyboard if they cannot be used.
+    let mut controllers = sdl.game_controller().ok().map(Controllers::new);
+    // The game is just as playable without sound, so a missing audio device is only reported if sound was asked for.
+    let sounds = Sounds::open(&sdl).map_err(|e| {
+        if config.hover_sound {
+            eprintln!("error: cannot play sounds: {}", e);
+        }
+    }).ok();
+
+    let screen_rect = Rect::new(0, 0, WINDOW_SIZE, WINDOW_SIZE);
+    let border_rect = Rect::new(BORDER_THICKNESS, BORDER_THICKNESS, WINDOW_SIZE - (BORDER_THICKNESS as u32 * 2), WINDOW_SIZE - (BORDER_THICKNESS as u32 * 2));
+
+    let mut rng = Rng::new(config.seed());
+    let state = match (&config.resume, &config.board) {
+        (Some(path), _) => match SaveData::read(path, &config.rules) {
+            Ok(save) => GameState::from_save(save, config.clock, Instant::now()),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(2);
+            },
+        },
+        (None, Some(board)) => {
+            println!("Challenge code for this position: {}", encode_challenge(board, &config.rules));
+            GameState::from_board(board.clone(), config.clock)
+        },
+        // The first game goes as if Blue had started the one before it, so that alternating games start with Red.
+        (None, None) => GameState::new(config.rules.squares(), config.clock, next_first(config.start_order, Square::O, &mut rng)),
+    };
+
+    let script = match &config.script {
+        Some(path) => script::read(path, &state.squares, state.to_move(), &lines, config.rules.size).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }),
+        None => Vec::new(),
+    };
+
+    let arrow_cursor = Cursor::from_system(SystemCursor::Arrow).unwrap();
+    let hand_cursor = Cursor::from_system(SystemCursor::Hand).unwrap();
+    let mut showing_hand = false;
+
+    let mut session = Session {
+        connection,
+        engine,
+        clipboard: Some(clipboard),
+        script: script.into(),
+        ..Session::new(&config, state, rng, theme, Instant::now())
+    };
+    // Analysis for the overlays is slow on big boards, so it is only redone after a move.
+    let mut forks = PositionCache::new();
+    let mut hint = PositionCache::new();
+    let mut tree_size = PositionCache::new();
+    let mut eval = PositionCache::new();
+    // The square the mouse was over last frame, so the hover sound only plays on moving to another one.
+    let mut hovered: Option<usize> = None;
+    let mut fps = FpsCounter::new();
+    // Whether the window has been shown, which it is once its first frame is ready.
+    let mut shown = false;
+    // How much the border is tinted toward Red and toward Blue, from 0 to 1.
+    let mut turn_tint = [0.0; 2];
+    // Animations that carry over from frame to frame move by the time since the last one, so that they take as long at
+    // any frame rate.
+    let mut last_frame = Instant::now();
+
+    'game: loop {
+        let now = Instant::now();
+        let elapsed = now - last_frame;
+        last_frame = now;
+        let events: Vec<Event> = event_pump.poll_iter().collect();
+        for event in &events {
+            if let Some(message) = controllers.as_mut().and_then(|controllers| controllers.handle(event)) {
+                session.toasts.push(message, now);
+            }
+        }
+        match session.tick(&mut config, &mut lines, events, now) {
+            Frame::Quit => break 'game,
+            Frame::Scoreboard => {
+                if let Some(current_match) = &session.current_match {
+                    current_match.draw(&mut canvas, &config);
+                }
+                canvas.present();
+                continue;
+            },
+            Frame::Ready => {
+                draw_ready(&mut canvas, session.ready.unwrap_or_default(), &session.palettes[session.palette], &config);
+                canvas.present();
+                if !shown {
+                    canvas.window_mut().show();
+                    shown = true;
+                }
+                continue;
+            },
+            Frame::Board => {},
+        }
+
+        // Only empty squares in a running game can be clicked, unless marks can be overwritten, so only those get the
+        // hand cursor.
+        let mouse = event_pump.mouse_state();
+        let layout = session.layout(&config);
+        let clickable = session.menu.is_none()
+            && session.state.freeze_until.is_none()
+            && session.remote != Some(session.state.to_move())
+            && square_at(&config, &layout, mouse.x(), mouse.y()).is_some_and(|square| {
+                session.state.squares[square] == Square::Empty || config.occupied == OccupiedClick::Overwrite
+            });
+        if clickable != showing_hand {
+            if clickable { hand_cursor.set() } else { arrow_cursor.set() }
+            showing_hand = clickable;
+        }
+
+        let win_effect = win_effect_progress(&session.state, now);
+        if let (true, Some((_, progress))) = (config.shake, win_effect) {
+            let (dx, dy) = shake_offset(progress);
+            canvas.set_viewport(Rect::new(dx, dy, WINDOW_SIZE, WINDOW_SIZE));
+        }
+
+        let theme = &session.palettes[session.palette];
+        canvas.clear();
+        fill_gradient(&mut canvas, screen_rect, theme.background_top, theme.background_bottom);
+        if config.turn_tint {
+            let to_move = Some(session.state.to_move()).filter(|_| session.state.freeze_until.is_none());
+            turn_tint = fade_turn_tint(turn_tint, to_move, elapsed);
+        }
+        let border = lerp_color(lerp_color(theme.border, theme.x, TURN_TINT * turn_tint[0]), theme.o, TURN_TINT * turn_tint[1]);
+        fill_rectangle(&mut canvas, border_rect, border);
+        fill_rectangle(&mut canvas, layout.area(), theme.playing_area);
+        if layout.is_panned() {
+            canvas.set_clip_rect(layout.area());
+        }
+
+        let squares = match &session.state.replay {
+            Some(replay) => replay.squares(),
+            None => session.state.squares.clone(),
+        };
+
+        // Once the game is over the whole board is shown, so the result can be seen.
+        let revealed = event_pump.keyboard_state().is_scancode_pressed(Scancode::Space);
+        let alphas = if config.blindfold && !revealed && session.state.freeze_until.is_none() {
+            blindfold_alphas(&session.state, now)
+        } else {
+            vec![255; squares.len()]
+        };
+        draw_board(&mut canvas, &squares, &alphas, &config, &layout, theme);
+        if let Some((index, progress)) = session.state.replay.as_ref().and_then(|replay| replay.changed(now)) {
+            draw_replay_change(&mut canvas, &config, &layout, index, progress);
+        }
+        if let Some(heatmap) = session.heatmap {
+            draw_heatmap(&mut canvas, &config, &layout, session.stats.heat(heatmap));
+        }
+        let hovering = session.hovered(&config, mouse.x(), mouse.y());
+        if let Some(index) = hovering {
+            // The grid color stands out against the playing area in any theme.
+            draw_hover(&mut canvas, &config, &layout, index, theme.grid);
+            if let (true, Some(sounds)) = (session.hover_sound && hovered != Some(index), &sounds) {
+                sounds.play_hover();
+            }
+        }
+        hovered = hovering;
+        if let (Some(index), None, None) = (session.cursor, &session.menu, session.state.freeze_until) {
+            let to_move = session.state.to_move();
+            draw_hover(&mut canvas, &config, &layout, index, if to_move == Square::X { theme.x } else { theme.o });
+        }
+        if session.show_forks && session.menu.is_none() && session.state.freeze_until.is_none() {
+            let (squares, to_move) = (&session.state.squares, session.state.to_move());
+            let color = if to_move == Square::X { theme.x } else { theme.o };
+            for &index in forks.get(squares, to_move, || fork_moves(squares, &lines, to_move)) {
+                draw_hover(&mut canvas, &config, &layout, index, color);
+            }
+        }
+        if session.show_eval && session.menu.is_none() && session.state.freeze_until.is_none() {
+            let (squares, to_move) = (&session.state.squares, session.state.to_move());
+            let evaluations = eval.get(squares, to_move, || evaluate_moves(squares, to_move, &config.rules));
+            draw_eval(&mut canvas, &config.rules, &layout, evaluations, to_move);
+        }
+        let to_move = session.state.to_move();
+        let local_turn = session.remote != Some(to_move) && !(config.ai && to_move == AI_PLAYER);
+        if session.show_hint && local_turn && session.menu.is_none() && session.state.freeze_until.is_none() {
+            let squares = &session.state.squares;
+            if let Some(index) = *hint.get(squares, to_move, || best_move_within(squares, to_move, &config.rules, config.think)) {
+                // The arrow comes down from the middle of the top border, between the two clocks.
+                let from = Point::new(WINDOW_SIZE as i32 / 2, BORDER_THICKNESS);
+                let color = if to_move == Square::X { theme.x } else { theme.o };
+                draw_arrow(&mut canvas, from, square_center(&config.rules, &layout, index), color);
+            }
+        }
+        if session.show_lines {
+            draw_winning_lines(&mut canvas, &lines, &config.rules, &layout);
+        }
+        canvas.set_clip_rect(None);
+        canvas.set_viewport(None);
+        // A replay shows the game being played again, so the board is only grayed out once it is over.
+        if session.state.ended == Some(None) && session.state.freeze_until.is_some() && session.state.replay.is_none() {
+            let showing_result = session.result.as_ref().is_some_and(|(_, until)| now < *until || config.hold_result);
+            draw_draw_overlay(&mut canvas, &layout, !showing_result);
+        }
+        if let (true, Some((winner, progress))) = (config.flash, win_effect) {
+            let color = if winner == Square::X { theme.x } else { theme.o };
+            let alpha = (160.0 * (1.0 - progress)) as u8;
+            fill_rectangle(&mut canvas, screen_rect, Color::RGBA(color.r, color.g, color.b, alpha));
+        }
+
+        match (&session.state.replay, session.state.freeze_until) {
+            _ if config.hold_result => {},
+            (Some(replay), _) => draw_countdown(&mut canvas, replay.remaining_freeze, config.restart),
+            (None, Some(freeze_until)) => draw_countdown(&mut canvas, freeze_until.saturating_duration_since(now), config.restart),
+            (None, None) => {},
+        }
+        if let Some(entry) = &session.entry {
+            // What is being typed shares the bottom border too, and is shown over everything else there.
+            draw_entry(&mut canvas, entry);
+        } else if let (Some(offered_by), Some(remote)) = (session.state.draw_offer, session.remote) {
+            if session.state.freeze_until.is_none() {
+                draw_draw_offer(&mut canvas, offered_by, remote, &config);
+            }
+        } else if let (Some(requested_by), Some(remote)) = (session.state.undo_request, session.remote) {
+            if session.state.freeze_until.is_none() {
+                draw_undo_request(&mut canvas, requested_by, remote, &config);
+            }
+        } else if let Some((player, streak)) = session.stats.streak().filter(|&(_, streak)| streak >= MIN_SHOWN_STREAK) {
+            // The streak shares the middle of the bottom border with the draw offer, which matters more while it lasts.
+            draw_streak(&mut canvas, player, streak, theme, &config);
+        }
+        if config.ai && session.show_lost && session.state.lost && session.state.freeze_until.is_none() {
+            draw_lost_hint(&mut canvas);
+        }
+        if let Some(thinking) = &session.thinking {
+            let elapsed = now.saturating_duration_since(thinking.started);
+            if elapsed >= Duration::from_millis(THINKING_DELAY_MILLIS) {
+                draw_thinking(&mut canvas, elapsed);
+            }
+        }
+        if config.drill {
+            let (best, played) = session.drill_score;
+            draw_drill_score(&mut canvas, best, played);
+        }
+        if config.grow.is_some() {
+            draw_level(&mut canvas, session.level, config.rules.size);
+        }
+        if let Some(clock) = &session.state.clock {
+            clock.draw(&mut canvas, session.state.to_move(), theme, &config);
+        }
+        match &session.result {
+            Some((text, until)) if now < *until || config.hold_result => draw_result(&mut canvas, text),
+            Some(_) => session.result = None,
+            None => {},
+        }
+        if session.confirming_quit {
+            draw_result(&mut canvas, "Quit? (Y/N)");
+        }
+        if session.show_debug {
+            let (squares, to_move) = (&session.state.squares, session.state.to_move());
+            let size = *tree_size.get(squares, to_move, || game_tree_size(squares, to_move, &config.rules));
+            draw_debug_overlay(&mut canvas, &session.state, size, fps.fps, now);
+        }
+        if let (true, Some(index)) = (session.show_tooltip, hovering) {
+            draw_tooltip(&mut canvas, index, session.state.squares[index], config.rules.size, Point::new(mouse.x(), mouse.y()));
+        }
+        if let Some(open_menu) = &session.menu {
+            open_menu.draw(&mut canvas);
+        }
+        session.toasts.draw(&mut canvas, now);
+        fps.tick(now);
+        canvas.present();
+        if !shown {
+            canvas.window_mut().show();
+            shown = true;
+        }
+    }
+
+    session.stats.print_summary(Instant::now(), &config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a session at the given time with a new game under the config, in which Red moves first.
+    fn session(config: &Config, start: Instant) -> Session {
+        let state = GameState::new(config.rules.squares(), None, Square::X);
+        Session::new(config, state, Rng::new(1), Theme::default(), start)
+    }
+
+    /// Returns a click on the middle of the square with the given index.
+    fn click(session: &Session, config: &Config, index: usize) -> Event {
+        let center = session.layout(config).center(index);
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x: center.x(),
+            y: center.y(),
+        }
+    }
+
+    /// Returns a right click on the middle of the square with the given index.
+    fn right_click(session: &Session, config: &Config, index: usize) -> Event {
+        match click(session, config, index) {
+            Event::MouseButtonDown { timestamp, window_id, which, clicks, x, y, .. } => {
+                Event::MouseButtonDown { timestamp, window_id, which, mouse_btn: MouseButton::Right, clicks, x, y }
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Plays the given squares in order, one tick apiece, starting at the given time, and returns the time of the last.
+    fn play(session: &mut Session, config: &mut Config, lines: &mut Vec<Line>, squares: &[usize], start: Instant) -> Instant {
+        let mut now = start;
+        for &index in squares {
+            now += Duration::from_millis(200);
+            let event = click(session, config, index);
+            assert!(matches!(session.tick(config, lines, vec![event], now), Frame::Board));
+        }
+        now
+    }
+
+    #[test]
+    fn tick_plays_a_win_and_a_draw_through_the_freeze() {
+        let mut config = Config::default();
+        let mut lines = winning_lines(&config.rules);
+        let start = Instant::now();
+        let mut session = session(&config, start);
+
+        // X takes the top row.
+        let won = play(&mut session, &mut config, &mut lines, &[0, 3, 1, 4, 2], start);
+        assert_eq!(session.state.ended, Some(Some(Square::X)));
+        assert_eq!(session.state.freeze_until, Some(won + config.restart));
+
+        // Clicks during the freeze are dropped, right up to its end.
+        let frozen = won + config.restart - Duration::from_millis(1);
+        let event = click(&session, &config, 5);
+        session.tick(&mut config, &mut lines, vec![event], frozen);
+        assert_eq!(session.state.history, vec![0, 3, 1, 4, 2]);
+
+        // Once the freeze is over the board is cleared for the next game.
+        let reset = won + config.restart + Duration::from_millis(1);
+        session.tick(&mut config, &mut lines, Vec::new(), reset);
+        assert_eq!(session.state.history, Vec::<usize>::new());
+        assert_eq!(session.state.ended, None);
+        assert_eq!(session.state.freeze_until, None);
+
+        let drawn = play(&mut session, &mut config, &mut lines, &[0, 4, 8, 1, 7, 6, 2, 5, 3], reset);
+        assert_eq!(session.state.ended, Some(None));
+        assert_eq!(session.state.freeze_until, Some(drawn + config.restart));
+        assert_eq!(session.stats.streak(), None);
+    }
+
+    #[test]
+    fn nothing_is_legal_while_the_game_is_frozen() {
+        let lines = winning_lines(&Rules::default());
+        let now = Instant::now();
+        let mut state = GameState::new(9, None, Square::X);
+        assert_eq!(state.legal_moves(&lines), (0..9).collect::<Vec<usize>>());
+        place(&mut state, 4, now);
+        assert_eq!(state.legal_moves(&lines), vec![0, 1, 2, 3, 5, 6, 7, 8]);
+        endgame(&mut state, Duration::from_secs(1), now);
+        assert_eq!(state.legal_moves(&lines), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn the_square_under_the_mouse_is_hovered_only_while_it_could_be_played() {
+        let config = Config::default();
+        let now = Instant::now();
+        let mut session = session(&config, now);
+        let center = session.layout(&config).center(7);
+        assert_eq!(session.hovered(&config, center.x(), center.y()), Some(7));
+        assert_eq!(session.hovered(&config, 0, 0), None);
+
+        session.menu = Some(Menu::default());
+        assert_eq!(session.hovered(&config, center.x(), center.y()), None);
+        session.menu = None;
+        endgame(&mut session.state, config.restart, now);
+        assert_eq!(session.hovered(&config, center.x(), center.y()), None);
+    }
+
+    #[test]
+    fn a_10x10_board_shrinks_to_fit_the_window() {
+        let layout = Layout::new(10, None, (0.5, 0.5));
+        assert!(!layout.is_panned());
+        assert_eq!(layout.square_size, PLAYING_AREA_SIZE / 10);
+        for index in 0..100 {
+            assert!(layout.shows(index));
+            let center = layout.center(index);
+            assert_eq!(layout.square_at(0, center.x(), center.y()), Some(index));
+        }
+    }
+
+    #[test]
+    fn a_10x10_board_pans_once_its_squares_would_be_too_small() {
+        let layout = Layout::new(10, Some(PLAYING_AREA_SIZE / 5), (0.0, 0.0));
+        assert!(layout.is_panned());
+        assert_eq!(layout.square_size, PLAYING_AREA_SIZE / 5);
+        assert!(layout.shows(0) && !layout.shows(99));
+        let center = layout.center(11);
+        assert_eq!(layout.square_at(0, center.x(), center.y()), Some(11));
+        assert_eq!(layout.square_at(0, layout.center(99).x(), layout.center(99).y()), None);
+    }
+
+    #[test]
+    fn clicks_in_the_gap_between_squares_are_ignored() {
+        let layout = Layout::new(3, None, (0.5, 0.5));
+        let gap = 20;
+        let (first, second) = (layout.square_rect(0, gap), layout.square_rect(1, gap));
+        assert_eq!(layout.square_at(gap, first.center().x(), first.center().y()), Some(0));
+        assert_eq!(layout.square_at(gap, second.left() + 1, second.top() + 1), Some(1));
+        let between = (first.right() + second.left()) / 2;
+        assert_eq!(layout.square_at(gap, between, first.center().y()), None);
+        // Without a gap the same point is on the edge of one of the two squares.
+        assert!(matches!(layout.square_at(0, between, first.center().y()), Some(0 | 1)));
+    }
+
+    #[test]
+    fn tiny_squares_keep_at_least_a_pixel() {
+        for width in 0..4 {
+            let inner = get_inner_rect(Rect::new(0, 0, width.max(1), width.max(1)));
+            assert!(inner.width() >= 1 && inner.height() >= 1, "width {}", width);
+            // The hover glow shrinks the rect once for each ring.
+            let innermost = HOVER_GLOW_ALPHAS.iter().fold(inner, |rect, _| get_inner_rect(rect));
+            assert!(innermost.width() >= 1 && innermost.height() >= 1, "width {}", width);
+        }
+        // A 4x4 board drawn 50 pixels across has squares of 12 pixels, which a gap cannot shrink away.
+        let layout = Layout { size: 4, square_size: 50 / 4, origin: Point::new(0, 0) };
+        for gap in [0, 6, 12, 100] {
+            for index in 0..16 {
+                let rect = get_inner_rect(layout.square_rect(index, gap));
+                assert!(rect.width() >= 1 && rect.height() >= 1, "gap {}", gap);
+            }
+        }
+        let layout = Layout::new(tic_tac_toe::notation::MAX_COLUMNS, None, (0.5, 0.5));
+        assert!(layout.square_rect(0, u32::MAX).width() >= 1);
+    }
+
+    #[test]
+    fn the_turn_tint_fades_by_time_not_by_frame() {
+        let fade = Duration::from_millis(TURN_TINT_FADE_MILLIS);
+        assert_eq!(fade_turn_tint([0.0, 1.0], Some(Square::X), fade), [1.0, 0.0]);
+        assert_eq!(fade_turn_tint([0.0, 1.0], Some(Square::X), fade / 2), [0.5, 0.5]);
+        assert_eq!(fade_turn_tint([0.5, 0.0], None, fade * 4), [0.0, 0.0]);
+
+        // The same time split over more frames fades just as far.
+        for frames in [1, 5, 25] {
+            let weights = (0..frames).fold([0.0, 1.0], |weights, _| fade_turn_tint(weights, Some(Square::X), fade / 2 / frames));
+            assert!((weights[0] - 0.5).abs() < 1e-9 && (weights[1] - 0.5).abs() < 1e-9, "{} frames", frames);
+        }
+        assert_eq!(fade_turn_tint([0.25, 0.75], Some(Square::O), Duration::ZERO), [0.25, 0.75]);
+    }
+
+    #[test]
+    fn the_win_effect_plays_out_by_the_time_given_to_tick() {
+        let effect = Duration::from_millis(WIN_EFFECT_MILLIS);
+        // However many frames are drawn in between, the effect is as far along as the time that has passed.
+        for frames in [1, 3, 30] {
+            let mut config = Config { shake: true, ..Config::default() };
+            let mut lines = winning_lines(&config.rules);
+            let start = Instant::now();
+            let mut session = session(&config, start);
+            let won = play(&mut session, &mut config, &mut lines, &[0, 3, 1, 4, 2], start);
+            assert_eq!(win_effect_progress(&session.state, won), Some((Square::X, 0.0)));
+
+            let mut now = won;
+            for _ in 0..frames {
+                now += effect / 2 / frames;
+                session.tick(&mut config, &mut lines, Vec::new(), now);
+            }
+            let (winner, progress) = win_effect_progress(&session.state, now).unwrap();
+            assert_eq!(winner, Square::X);
+            assert!((progress - 0.5).abs() < 1e-9, "{} frames", frames);
+
+            session.tick(&mut config, &mut lines, Vec::new(), won + effect);
+            assert_eq!(win_effect_progress(&session.state, won + effect), None);
+        }
+    }
+
+    #[test]
+    fn the_board_and_its_squares_are_centered() {
+        for size in 1..=12 {
+            let layout = Layout::new(size, None, (0.5, 0.5));
+            let left_over = PLAYING_AREA_SIZE - layout.board_size();
+            let left = layout.origin.x() - PLAYING_AREA_OFFSET as i32;
+            assert!((left_over as i32 - 2 * left).abs() <= 1, "size {}", size);
+        }
+        for width in 3..=12 {
+            let cell = Rect::new(10, 20, width, width + 1);
+            assert_eq!(get_inner_rect(cell).center(), cell.center());
+        }
+    }
+
+    #[test]
+    fn the_move_cap_draws_the_game() {
+        let mut config = Config { max_moves: Some(4), ..Config::default() };
+        let mut lines = winning_lines(&config.rules);
+        let start = Instant::now();
+        let mut session = session(&config, start);
+
+        play(&mut session, &mut config, &mut lines, &[0, 4, 8], start);
+        assert_eq!(session.state.ended, None);
+        let capped = play(&mut session, &mut config, &mut lines, &[2], start + Duration::from_secs(1));
+        assert_eq!(session.state.ended, Some(None));
+        assert_eq!(session.state.freeze_until, Some(capped + config.restart));
+        assert_eq!(session.result.as_ref().map(|(message, _)| message.as_str()), Some("Draw by move limit!"));
+    }
+
+    #[test]
+    fn a_drawn_game_goes_to_the_tie_breaker() {
+        let mut config = Config { tie_breaker: Some(tic_tac_toe::board::TieBreaker::Center), ..Config::default() };
+        let mut lines = winning_lines(&config.rules);
+        let start = Instant::now();
+        let mut session = session(&config, start);
+
+        play(&mut session, &mut config, &mut lines, &[0, 4, 8, 1, 7, 6, 2, 5, 3], start);
+        assert_eq!(session.state.ended, Some(Some(Square::O)));
+        assert_eq!(session.result.as_ref().map(|(message, _)| message.as_str()), Some("Draw! Blue wins the tie-breaker!"));
+    }
+
+    #[test]
+    fn the_sandbox_never_ends_the_game_and_right_clicks_clear() {
+        let mut config = Config { sandbox: true, ..Config::default() };
+        let mut lines = winning_lines(&config.rules);
+        let start = Instant::now();
+        let mut session = session(&config, start);
+
+        let now = play(&mut session, &mut config, &mut lines, &[0, 3, 1, 4, 2], start);
+        assert_eq!(session.state.ended, None);
+        assert_eq!(session.state.freeze_until, None);
+
+        let event = right_click(&session, &config, 1);
+        session.tick(&mut config, &mut lines, vec![event], now);
+        assert_eq!(session.state.squares[1], Square::Empty);
+        assert_eq!(session.state.history, vec![0, 3, 4, 2]);
+        let event = right_click(&session, &config, 5);
+        session.tick(&mut config, &mut lines, vec![event], now);
+        assert_eq!(session.state.history, vec![0, 3, 4, 2]);
+    }
+
+    #[test]
+    fn lost_positions_are_only_checked_while_the_hint_is_shown() {
+        let mut config = Config { ai: true, ..Config::default() };
+        let mut lines = winning_lines(&config.rules);
+        let start = Instant::now();
+        let mut session = session(&config, start);
+
+        let now = play(&mut session, &mut config, &mut lines, &[0], start);
+        assert!(session.state.lost_check.is_none());
+
+        session.show_lost = true;
+        session.tick(&mut config, &mut lines, Vec::new(), now);
+        let check = session.state.lost_check.as_ref().unwrap();
+        assert_eq!(check.squares, session.state.squares);
+    }
+
+    #[test]
+    fn the_winner_is_worked_out_again_only_once_the_board_changes() {
+        let lines = winning_lines(&Rules::default());
+        let now = Instant::now();
+        let mut state = GameState::new(9, None, Square::X);
+        for square in [0, 3, 1, 4] {
+            place(&mut state, square, now);
+        }
+        assert_eq!(state.winner(&lines), None);
+        assert_eq!(state.board_winner, Some(None));
+
+        place(&mut state, 2, now);
+        assert_eq!(state.board_winner, None);
+        assert_eq!(state.winner(&lines), Some(Square::X));
+        take_back(&mut state);
+        assert_eq!(state.winner(&lines), None);
+    }
+
+    #[test]
+    fn freeze_and_clocks_carry_over_a_save() {
+        let start = Instant::now();
+        let mut state = GameState::new(9, Some(Duration::from_secs(60)), Square::X);
+        place(&mut state, 4, start);
+        if let Some(clock) = &mut state.clock {
+            clock.run(Square::O, start);
+            clock.run(Square::O, start + Duration::from_secs(5));
+        }
+        endgame(&mut state, Duration::from_millis(2000), start + Duration::from_secs(5));
+
+        let saved = state.to_save(start + Duration::from_millis(5500));
+        let resumed = GameState::from_save(saved, Some(Duration::from_secs(60)), start + Duration::from_secs(100));
+        let remaining = resumed.freeze_until.map(|freeze_until| freeze_until - (start + Duration::from_secs(100)));
+        assert_eq!(remaining, Some(Duration::from_millis(1500)));
+        let clock = resumed.clock.as_ref().unwrap();
+        assert_eq!(clock.remaining(Square::X), Duration::from_secs(60));
+        assert_eq!(clock.remaining(Square::O), Duration::from_millis(54_500));
+    }
+
+    #[test]
+    fn replay_steps_follow_the_given_time() {
+        let start = Instant::now();
+        let mut replay = Replay::new(vec![Square::Empty; 9], Square::X, vec![4, 0], Duration::from_secs(1), start);
+        assert!(replay.step(start));
+        assert_eq!(replay.shown, 0);
+        let step = Duration::from_millis(REPLAY_STEP_MILLIS);
+        assert!(replay.step(start + step));
+        assert_eq!(replay.shown, 1);
+    }
+}
@@ -0,0 +1,170 @@
+//! The pause menu. It can be driven entirely by the keyboard: Tab and the arrow keys move the focus ring between the
+//! options and Enter picks the focused one. The mouse focuses an option by hovering over it and picks it by clicking.
+
+use crate::{fill_rectangle, font, WINDOW_SIZE};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+/// The width and height of each option, in pixels.
+const OPTION_WIDTH: u32 = 320;
+const OPTION_HEIGHT: u32 = 56;
+
+/// The space between two options, in pixels.
+const OPTION_SPACING: u32 = 16;
+
+/// The width of the focus ring, in pixels.
+const FOCUS_RING_THICKNESS: u32 = 3;
+
+/// The size of each font pixel in an option, in screen pixels.
+const MENU_TEXT_SCALE: u32 = 4;
+
+/// What picking a menu option does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuAction {
+    Resume,
+    NewGame,
+    Save,
+    Quit,
+}
+
+/// The options in the order they are shown.
+const OPTIONS: [(&str, MenuAction); 4] = [
+    ("resume", MenuAction::Resume),
+    ("new game", MenuAction::NewGame),
+    ("save", MenuAction::Save),
+    ("quit", MenuAction::Quit),
+];
+
+#[derive(Default)]
+pub struct Menu {
+    /// The index of the option the focus ring is on.
+    focused: usize,
+}
+
+/// Returns the area of the option with the given index, with the options stacked in the middle of the window.
+fn option_rect(index: usize) -> Rect {
+    let count = OPTIONS.len() as u32;
+    let total_height = count * OPTION_HEIGHT + (count - 1) * OPTION_SPACING;
+    let x = (WINDOW_SIZE - OPTION_WIDTH) as i32 / 2;
+    let y = (WINDOW_SIZE - total_height) as i32 / 2 + (index as u32 * (OPTION_HEIGHT + OPTION_SPACING)) as i32;
+    Rect::new(x, y, OPTION_WIDTH, OPTION_HEIGHT)
+}
+
+/// Returns the index of the option under the given coordinates, if any.
+fn option_at(x: i32, y: i32) -> Option<usize> {
+    (0..OPTIONS.len()).find(|&i| option_rect(i).contains_point((x, y)))
+}
+
+impl Menu {
+    /// Moves the focus ring the given number of options, wrapping around at either end.
+    fn move_focus(&mut self, offset: isize) {
+        let count = OPTIONS.len() as isize;
+        self.focused = (self.focused as isize + offset).rem_euclid(count) as usize;
+    }
+
+    /// Handles an event, returning the action of the option it picked, if any. M closes the menu again.
+    pub fn handle(&mut self, event: &Event) -> Option<MenuAction> {
+        match *event {
+            Event::KeyDown { keycode: Some(Keycode::Tab), keymod, .. } => {
+                let backwards = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                self.move_focus(if backwards { -1 } else { 1 });
+            },
+            Event::KeyDown { keycode: Some(Keycode::Down), .. } => self.move_focus(1),
+            Event::KeyDown { keycode: Some(Keycode::Up), .. } => self.move_focus(-1),
+            Event::KeyDown { keycode: Some(Keycode::Return), .. } | Event::KeyDown { keycode: Some(Keycode::KpEnter), .. } => {
+                return Some(OPTIONS[self.focused].1);
+            },
+            Event::KeyDown { keycode: Some(Keycode::M), .. } => return Some(MenuAction::Resume),
+            Event::MouseMotion { x, y, .. } => {
+                if let Some(index) = option_at(x, y) {
+                    self.focused = index;
+                }
+            },
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                if let Some(index) = option_at(x, y) {
+                    return Some(OPTIONS[index].1);
+                }
+            },
+            _ => {},
+        }
+        None
+    }
+
+    /// Draws the menu over a dimmed copy of whatever is behind it.
+    pub fn draw(&self, canvas: &mut WindowCanvas) {
+        fill_rectangle(canvas, Rect::new(0, 0, WINDOW_SIZE, WINDOW_SIZE), Color::RGBA(0, 0, 0, 176));
+        for (index, (label, _)) in OPTIONS.iter().enumerate() {
+            let rect = option_rect(index);
+            if index == self.focused {
+                let ring = FOCUS_RING_THICKNESS as i32;
+                let outer = Rect::new(rect.x() - ring, rect.y() - ring, rect.width() + FOCUS_RING_THICKNESS * 2, rect.height() + FOCUS_RING_THICKNESS * 2);
+                fill_rectangle(canvas, outer, Color::WHITE);
+            }
+            fill_rectangle(canvas, rect, Color::RGB(48, 48, 48));
+            let text_x = rect.x() + (rect.width() - font::text_width(label, MENU_TEXT_SCALE)) as i32 / 2;
+            let text_y = rect.y() + (rect.height() - font::GLYPH_HEIGHT * MENU_TEXT_SCALE) as i32 / 2;
+            font::draw_text(canvas, label, text_x, text_y, MENU_TEXT_SCALE, Color::WHITE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the event of pressing the given key with the given modifiers.
+    fn key(keycode: Keycode, keymod: Mod) -> Event {
+        Event::KeyDown { timestamp: 0, window_id: 0, keycode: Some(keycode), scancode: None, keymod, repeat: false }
+    }
+
+    /// Returns what pressing the given keys in order picks, if anything, once they have all been pressed.
+    fn press(keys: &[(Keycode, Mod)]) -> Option<MenuAction> {
+        let mut menu = Menu::default();
+        keys.iter().map(|&(keycode, keymod)| menu.handle(&key(keycode, keymod))).last().flatten()
+    }
+
+    #[test]
+    fn enter_picks_the_focused_option() {
+        assert_eq!(press(&[(Keycode::Return, Mod::NOMOD)]), Some(MenuAction::Resume));
+        assert_eq!(press(&[(Keycode::Down, Mod::NOMOD), (Keycode::KpEnter, Mod::NOMOD)]), Some(MenuAction::NewGame));
+        assert_eq!(
+            press(&[(Keycode::Tab, Mod::NOMOD), (Keycode::Tab, Mod::NOMOD), (Keycode::Return, Mod::NOMOD)]),
+            Some(MenuAction::Save),
+        );
+    }
+
+    #[test]
+    fn the_focus_wraps_around_at_either_end() {
+        assert_eq!(press(&[(Keycode::Up, Mod::NOMOD), (Keycode::Return, Mod::NOMOD)]), Some(MenuAction::Quit));
+        assert_eq!(press(&[(Keycode::Tab, Mod::LSHIFTMOD), (Keycode::Return, Mod::NOMOD)]), Some(MenuAction::Quit));
+        let down = (Keycode::Down, Mod::NOMOD);
+        assert_eq!(press(&[down, down, down, down, (Keycode::Return, Mod::NOMOD)]), Some(MenuAction::Resume));
+    }
+
+    #[test]
+    fn moving_the_focus_picks_nothing() {
+        assert_eq!(press(&[(Keycode::Down, Mod::NOMOD), (Keycode::Tab, Mod::NOMOD)]), None);
+    }
+
+    #[test]
+    fn hovering_moves_the_focus_to_the_option_under_the_mouse() {
+        let mut menu = Menu::default();
+        let center = option_rect(3).center();
+        let hover = Event::MouseMotion {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mousestate: sdl2::mouse::MouseState::from_sdl_state(0),
+            x: center.x(),
+            y: center.y(),
+            xrel: 0,
+            yrel: 0,
+        };
+        assert_eq!(menu.handle(&hover), None);
+        assert_eq!(menu.handle(&key(Keycode::Return, Mod::NOMOD)), Some(MenuAction::Quit));
+    }
+}
@@ -0,0 +1,117 @@
+//! Playing against someone on another computer over TCP. Messages are sent one per line as plain text.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+/// A message between the two players.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Message {
+    /// The sender played in the square with the given index.
+    Move(usize),
+    /// The sender offers to end the game as a draw.
+    OfferDraw,
+    /// The sender accepts the draw offered to them.
+    AcceptDraw,
+    /// The sender declines the draw offered to them.
+    DeclineDraw,
+    /// The sender asks to take back the last move, which they played.
+    RequestUndo,
+    /// The sender agrees to take back the last move.
+    AcceptUndo,
+    /// The sender refuses to take back the last move.
+    DeclineUndo,
+}
+
+impl Message {
+    /// Returns the line the message is sent as, without the newline.
+    fn encode(self) -> String {
+        match self {
+            Message::Move(index) => format!("move {}", index),
+            Message::OfferDraw => "offer-draw".to_string(),
+            Message::AcceptDraw => "accept-draw".to_string(),
+            Message::DeclineDraw => "decline-draw".to_string(),
+            Message::RequestUndo => "request-undo".to_string(),
+            Message::AcceptUndo => "accept-undo".to_string(),
+            Message::DeclineUndo => "decline-undo".to_string(),
+        }
+    }
+
+    /// Parses a line sent by the other player, or returns None if it is not a message.
+    fn parse(line: &str) -> Option<Self> {
+        match line.split_once(' ') {
+            Some(("move", index)) => index.parse().ok().map(Message::Move),
+            None if line == "offer-draw" => Some(Message::OfferDraw),
+            None if line == "accept-draw" => Some(Message::AcceptDraw),
+            None if line == "decline-draw" => Some(Message::DeclineDraw),
+            None if line == "request-undo" => Some(Message::RequestUndo),
+            None if line == "accept-undo" => Some(Message::AcceptUndo),
+            None if line == "decline-undo" => Some(Message::DeclineUndo),
+            _ => None,
+        }
+    }
+}
+
+/// A connection to the other player.
+pub struct Connection {
+    stream: TcpStream,
+    /// Text received that does not yet make up a whole line.
+    received: String,
+}
+
+impl Connection {
+    /// Waits for the other player to connect on the given port.
+    pub fn host(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        println!("Waiting for an opponent on port {}...", port);
+        let (stream, address) = listener.accept()?;
+        println!("{} connected", address);
+        Self::new(stream)
+    }
+
+    /// Connects to a player hosting a game at the given address.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        Self::new(TcpStream::connect(address)?)
+    }
+
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self { stream, received: String::new() })
+    }
+
+    /// Sends a message to the other player.
+    pub fn send(&mut self, message: Message) -> io::Result<()> {
+        // Messages are tiny, so a write that would block is retried rather than buffered.
+        let line = format!("{}\n", message.encode());
+        let mut bytes = line.as_bytes();
+        while !bytes.is_empty() {
+            match self.stream.write(bytes) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => bytes = &bytes[written..],
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::yield_now(),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the messages that have arrived since the last call, without waiting for more.
+    /// Lines that are not messages are skipped, and an error means the other player is gone.
+    pub fn receive(&mut self) -> io::Result<Vec<Message>> {
+        let mut buffer = [0; 256];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::ConnectionAborted.into()),
+                Ok(read) => self.received.push_str(&String::from_utf8_lossy(&buffer[..read])),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        let mut messages = Vec::new();
+        while let Some(end) = self.received.find('\n') {
+            let line: String = self.received.drain(..=end).collect();
+            messages.extend(Message::parse(line.trim()));
+        }
+        Ok(messages)
+    }
+}
@@ -0,0 +1,96 @@
+//! A compact notation for games. Each move is written as the column letter followed by the row number of its square,
+//! counting from the top-left, so the center of the 3x3 board is b2. Moves are separated by spaces: "b2 a1 c3".
+//! Boards are limited to 26 columns by the alphabet.
+
+use std::fmt;
+
+/// The most columns a board written in notation can have.
+pub const MAX_COLUMNS: usize = 26;
+
+/// An error in a game written in notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The move at the given position (counting from zero) is not a coordinate on the board.
+    InvalidSquare { position: usize, text: String },
+    /// The move at the given position plays a square that was already played.
+    RepeatedSquare { position: usize, text: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidSquare { position, text } => write!(f, "move {} ('{}') is not a square on the board", position + 1, text),
+            ParseError::RepeatedSquare { position, text } => write!(f, "move {} ('{}') plays a square that was already played", position + 1, text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Returns the name of the square with the given index on a board of the given size, such as b2.
+pub fn square_name(index: usize, size: usize) -> String {
+    let col = index % size;
+    let row = index / size;
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+/// Returns the index of the square with the given name on a board of the given size, or None if there is no such square.
+pub fn parse_square(name: &str, size: usize) -> Option<usize> {
+    let mut chars = name.chars();
+    let col = chars.next()?.to_ascii_lowercase();
+    if !col.is_ascii_lowercase() {
+        return None;
+    }
+    let col = (col as u8 - b'a') as usize;
+    let row: usize = chars.as_str().parse().ok()?;
+    if col >= size || row == 0 || row > size {
+        return None;
+    }
+    Some(((row - 1) * size) + col)
+}
+
+/// Writes the moves of a game on a board of the given size in notation.
+pub fn encode_game(moves: &[usize], size: usize) -> String {
+    moves.iter().map(|&index| square_name(index, size)).collect::<Vec<String>>().join(" ")
+}
+
+/// Reads the moves of a game on a board of the given size written in notation.
+pub fn decode_game(text: &str, size: usize) -> Result<Vec<usize>, ParseError> {
+    let mut moves = Vec::new();
+    for (position, token) in text.split_whitespace().enumerate() {
+        let index = parse_square(token, size).ok_or_else(|| ParseError::InvalidSquare { position, text: token.to_string() })?;
+        if moves.contains(&index) {
+            return Err(ParseError::RepeatedSquare { position, text: token.to_string() });
+        }
+        moves.push(index);
+    }
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_round_trip_through_notation() {
+        for (moves, size) in [(vec![], 3), (vec![4, 0, 8, 2, 6], 3), (vec![15, 0, 5, 10], 4), (vec![675, 0, 25, 650], MAX_COLUMNS)] {
+            assert_eq!(decode_game(&encode_game(&moves, size), size), Ok(moves));
+        }
+    }
+
+    #[test]
+    fn squares_are_named_by_column_then_row() {
+        assert_eq!(encode_game(&[4, 0, 8], 3), "b2 a1 c3");
+        assert_eq!(square_name(675, MAX_COLUMNS), "z26");
+        assert_eq!(parse_square("B2", 3), Some(4));
+        assert_eq!(parse_square("d1", 3), None);
+        assert_eq!(parse_square("a4", 3), None);
+        assert_eq!(parse_square("a0", 3), None);
+    }
+
+    #[test]
+    fn bad_moves_are_reported_by_position() {
+        assert_eq!(decode_game("b2 x9", 3), Err(ParseError::InvalidSquare { position: 1, text: "x9".to_string() }));
+        assert_eq!(decode_game("b2 a1 b2", 3), Err(ParseError::RepeatedSquare { position: 2, text: "b2".to_string() }));
+    }
+}
@@ -0,0 +1,72 @@
+//! Headless AI-vs-AI games from random starting positions, for measuring how much moving first is worth.
+
+use crate::player_name;
+use tic_tac_toe::analysis::best_move;
+use tic_tac_toe::board::{get_winner, next_mark, random_board, winning_lines, Rules, Square};
+use tic_tac_toe::rng::Rng;
+
+/// The results of the games one player moved first in.
+#[derive(Default)]
+struct Tally {
+    games: u32,
+    wins: u32,
+    losses: u32,
+    draws: u32,
+}
+
+impl Tally {
+    /// Returns the given count as a percentage of the games.
+    fn percent(&self, count: u32) -> f64 {
+        if self.games == 0 { 0.0 } else { count as f64 * 100.0 / self.games as f64 }
+    }
+}
+
+/// Plays the game out with the best move for both sides, returning the winner or None for a draw.
+fn play_out(mut squares: Vec<Square>, rules: &Rules) -> Option<Square> {
+    let lines = winning_lines(rules);
+    loop {
+        if let Some(winner) = get_winner(&squares, &lines) {
+            return Some(winner);
+        }
+        let to_move = next_mark(&squares);
+        let index = best_move(&squares, to_move, rules)?;
+        squares[index] = to_move;
+    }
+}
+
+/// Plays the given number of games and prints how they went for whoever moved first.
+pub fn run(games: u32, seed: u64, rules: &Rules) {
+    let mut rng = Rng::new(seed);
+    let mut tallies = [Tally::default(), Tally::default()];
+    for _ in 0..games {
+        // Anything from the empty board to one move short of full.
+        let fill_fraction = rng.below(rules.squares()) as f64 / rules.squares() as f64;
+        let start = random_board(rules, &mut rng, fill_fraction);
+        let first = next_mark(&start);
+        let tally = &mut tallies[if first == Square::X { 0 } else { 1 }];
+        tally.games += 1;
+        match play_out(start, rules) {
+            Some(winner) if winner == first => tally.wins += 1,
+            Some(_) => tally.losses += 1,
+            None => tally.draws += 1,
+        }
+    }
+
+    println!("Played {} games from random starting positions (seed {})", games, seed);
+    for (player, tally) in [Square::X, Square::O].iter().zip(tallies.iter()) {
+        println!(
+            "{} moved first in {} games: won {:.1}%, lost {:.1}%, drew {:.1}%",
+            player_name(*player),
+            tally.games,
+            tally.percent(tally.wins),
+            tally.percent(tally.losses),
+            tally.percent(tally.draws),
+        );
+    }
+    let total = Tally {
+        games,
+        wins: tallies.iter().map(|tally| tally.wins).sum(),
+        ..Tally::default()
+    };
+    println!("First player win rate: {:.1}%", total.percent(total.wins));
+}
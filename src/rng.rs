@@ -0,0 +1,33 @@
+//! A small seeded random number generator, so that anything random can be reproduced from its seed.
+
+/// An xorshift64* generator. It is not suitable for anything security related.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Returns a generator that always produces the same numbers for the same seed.
+    pub fn new(seed: u64) -> Self {
+        // The state can never be zero, and mixing the seed keeps small seeds from starting out similar.
+        Self { state: (seed ^ 0x9E37_79B9_7F4A_7C15).max(1) }
+    }
+
+    /// Returns the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a random number in 0..bound. The bound must not be zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns a random number in 0.0..1.0.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,150 @@
+//! Saved games. A save is a small text file of `key = value` lines. Timers are stored as the time that was left on them
+//! rather than as a point in time, so that they pick up where they left off when the game is resumed.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tic_tac_toe::board::{format_board, is_legal_position, next_mark, parse_board, Rules, Square};
+
+/// Everything needed to restore a game in progress.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveData {
+    /// The board the game started from.
+    pub start: Vec<Square>,
+    /// The player who moved first from the start board.
+    pub first: Square,
+    /// The square indices played so far, in order.
+    pub moves: Vec<usize>,
+    /// The time left until the next game starts, if the game was over.
+    pub freeze_remaining: Option<Duration>,
+    /// The time Red and Blue had left on their clocks, in that order, if the game was timed.
+    pub clock_remaining: Option<[Duration; 2]>,
+}
+
+impl SaveData {
+    /// Writes the save to a file, replacing it if it exists.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("cannot save to '{}': {}", path.display(), e))
+    }
+
+    /// Reads a save from a file, checking that the game in it could have been played under the given rules.
+    pub fn read(path: &Path, rules: &Rules) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read save '{}': {}", path.display(), e))?;
+        Self::parse(&text, rules).map_err(|e| format!("invalid save '{}': {}", path.display(), e))
+    }
+
+    /// Parses the contents of a save file.
+    pub fn parse(text: &str, rules: &Rules) -> Result<Self, String> {
+        let mut start = None;
+        let mut first = None;
+        let mut moves = Vec::new();
+        let mut freeze_remaining = None;
+        let mut clocks = [None, None];
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("expected key = value, found '{}'", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "start" => start = Some(parse_board(value, rules.size).ok_or_else(|| format!("'{}' is not a board", value))?),
+                "first" => {
+                    first = Some(match value {
+                        "X" => Square::X,
+                        "O" => Square::O,
+                        _ => return Err(format!("'{}' is not a player", value)),
+                    });
+                },
+                "moves" => {
+                    moves = value
+                        .split_whitespace()
+                        .map(|index| index.parse().map_err(|_| format!("'{}' is not a square index", index)))
+                        .collect::<Result<_, _>>()?;
+                },
+                "freeze_remaining_ms" => {
+                    let millis = value.parse().map_err(|_| format!("'{}' is not a number of milliseconds", value))?;
+                    freeze_remaining = Some(Duration::from_millis(millis));
+                },
+                key @ ("x_clock_ms" | "o_clock_ms") => {
+                    let millis = value.parse().map_err(|_| format!("'{}' is not a number of milliseconds", value))?;
+                    clocks[if key == "x_clock_ms" { 0 } else { 1 }] = Some(Duration::from_millis(millis));
+                },
+                other => return Err(format!("unknown key '{}'", other)),
+            }
+        }
+        let start = start.ok_or("missing start board")?;
+        if !is_legal_position(&start, next_mark(&start), rules) {
+            return Err("the start board is not a legal position".to_string());
+        }
+        // Either player can start from an empty board, but otherwise the marks on the board decide who is next.
+        // Saves from before the first player was recorded always started with the usual player.
+        let first = first.unwrap_or_else(|| next_mark(&start));
+        if first != next_mark(&start) && start.iter().any(|&square| square != Square::Empty) {
+            return Err(format!("{:?} cannot move first from the start board", first));
+        }
+        let mut squares = start.clone();
+        for (n, &index) in moves.iter().enumerate() {
+            if squares.get(index) != Some(&Square::Empty) {
+                return Err(format!("square {} cannot be played", index));
+            }
+            squares[index] = if n.is_multiple_of(2) { first } else { first.opponent() };
+        }
+        let clock_remaining = match clocks {
+            [Some(x), Some(o)] => Some([x, o]),
+            [None, None] => None,
+            _ => return Err("only one player's clock is saved".to_string()),
+        };
+        Ok(Self { start, first, moves, freeze_remaining, clock_remaining })
+    }
+}
+
+impl std::fmt::Display for SaveData {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let moves: Vec<String> = self.moves.iter().map(|index| index.to_string()).collect();
+        writeln!(f, "start = {}", format_board(&self.start))?;
+        writeln!(f, "first = {:?}", self.first)?;
+        writeln!(f, "moves = {}", moves.join(" "))?;
+        if let Some(remaining) = self.freeze_remaining {
+            writeln!(f, "freeze_remaining_ms = {}", remaining.as_millis())?;
+        }
+        if let Some([x, o]) = self.clock_remaining {
+            writeln!(f, "x_clock_ms = {}", x.as_millis())?;
+            writeln!(f, "o_clock_ms = {}", o.as_millis())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timers_survive_a_save_and_load() {
+        let rules = Rules::default();
+        let mut start = vec![Square::Empty; rules.squares()];
+        start[4] = Square::X;
+        let save = SaveData {
+            start,
+            first: Square::O,
+            moves: vec![0, 8],
+            freeze_remaining: Some(Duration::from_millis(1500)),
+            clock_remaining: Some([Duration::from_millis(41_250), Duration::from_millis(7_003)]),
+        };
+        let path = std::env::temp_dir().join(format!("tic-tac-toe-save-test-{}.save", std::process::id()));
+        save.write(&path).unwrap();
+        let loaded = SaveData::read(&path, &rules);
+        fs::remove_file(&path).ok();
+        assert_eq!(loaded, Ok(save));
+    }
+
+    #[test]
+    fn saves_without_clocks_still_load() {
+        let loaded = SaveData::parse("start = .........\nfirst = X\nmoves = 4\n", &Rules::default()).unwrap();
+        assert_eq!(loaded.clock_remaining, None);
+        assert_eq!(loaded.freeze_remaining, None);
+    }
+
+    #[test]
+    fn a_save_with_one_clock_is_rejected() {
+        let text = "start = .........\nfirst = X\nmoves = 4\nx_clock_ms = 1000\n";
+        assert!(SaveData::parse(text, &Rules::default()).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{config::Config, font, WINDOW_SIZE};
+use sdl2::pixels::Color;
+use sdl2::render::WindowCanvas;
+use tic_tac_toe::board::Square;
+
+/// The size of each font pixel on the scoreboard, in screen pixels.
+const SCOREBOARD_TEXT_SCALE: u32 = 4;
+
+/// A series of games won by whoever takes the majority of them.
+pub struct Match {
+    best_of: u32,
+    /// The winner of each game played so far, or None for a draw.
+    results: Vec<Option<Square>>,
+}
+
+impl Match {
+    pub fn new(best_of: u32) -> Self {
+        Self { best_of, results: Vec::new() }
+    }
+
+    /// Records the result of a game.
+    pub fn record(&mut self, winner: Option<Square>) {
+        self.results.push(winner);
+    }
+
+    /// Returns the number of games the player has won.
+    pub fn wins(&self, player: Square) -> u32 {
+        self.results.iter().filter(|&&result| result == Some(player)).count() as u32
+    }
+
+    /// Returns the player who has won the majority of the games, if any.
+    pub fn winner(&self) -> Option<Square> {
+        [Square::X, Square::O].iter().copied().find(|&player| self.wins(player) > self.best_of / 2)
+    }
+
+    /// Returns whether the match has been decided, or every game has been played.
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some() || self.results.len() as u32 >= self.best_of
+    }
+
+    /// Starts the match over once it is over.
+    pub fn reset_if_over(&mut self) {
+        if self.is_over() {
+            self.results.clear();
+        }
+    }
+
+    /// Draws the result of each game and the running score, centered in the window.
+    pub fn draw(&self, canvas: &mut WindowCanvas, config: &Config) {
+        let mut lines: Vec<String> = self
+            .results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| match result {
+                Some(winner) => format!("game {}: {}", i + 1, config.name(*winner)),
+                None => format!("game {}: draw", i + 1),
+            })
+            .collect();
+        lines.push(String::new());
+        lines.push(format!(
+            "{} {} - {} {}",
+            config.name(Square::X),
+            self.wins(Square::X),
+            self.wins(Square::O),
+            config.name(Square::O),
+        ));
+        if self.is_over() {
+            lines.push(match self.winner() {
+                Some(winner) => format!("{} wins the match!", config.name(winner)),
+                None => "match drawn!".to_string(),
+            });
+        }
+
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+        let line_height = (font::GLYPH_HEIGHT + 3) * SCOREBOARD_TEXT_SCALE;
+        let top = (WINDOW_SIZE as i32 - (line_height * lines.len() as u32) as i32) / 2;
+        for (i, line) in lines.iter().enumerate() {
+            let x = (WINDOW_SIZE as i32 - font::text_width(line, SCOREBOARD_TEXT_SCALE) as i32) / 2;
+            font::draw_text(canvas, line, x, top + (line_height * i as u32) as i32, SCOREBOARD_TEXT_SCALE, Color::WHITE);
+        }
+    }
+}
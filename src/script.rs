@@ -0,0 +1,43 @@
+//! Scripted openings: a file of moves that is played out at the start of the first game, after which the game carries
+//! on as usual. Moves are written as square indices counting from zero, or as names like b2, separated by spaces or
+//! line breaks. Anything after a # is a comment.
+
+use std::fs;
+use std::path::Path;
+use tic_tac_toe::board::{game_over, Line, Square};
+use tic_tac_toe::notation::parse_square;
+
+/// Reads a script from a file, checking that its moves can be played one after another from the given board, which is
+/// on a board of the given size.
+pub fn read(path: &Path, squares: &[Square], to_move: Square, lines: &[Line], size: usize) -> Result<Vec<usize>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read script '{}': {}", path.display(), e))?;
+    parse(&text, squares, to_move, lines, size).map_err(|(line, e)| format!("invalid script '{}' on line {}: {}", path.display(), line, e))
+}
+
+/// Parses the contents of a script file, returning the line number of the first bad move along with what is wrong with
+/// it.
+fn parse(text: &str, squares: &[Square], mut to_move: Square, lines: &[Line], size: usize) -> Result<Vec<usize>, (usize, String)> {
+    let mut squares = squares.to_vec();
+    let mut moves = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        for word in line.split_whitespace() {
+            let error = |e: String| (number + 1, e);
+            let index = match word.parse::<usize>() {
+                Ok(index) if index < squares.len() => index,
+                Ok(_) => return Err(error(format!("there is no square {} on the board", word))),
+                Err(_) => parse_square(word, size).ok_or_else(|| error(format!("'{}' is not a square", word)))?,
+            };
+            if game_over(&squares, lines) {
+                return Err(error(format!("the game is over before {} can be played", word)));
+            }
+            if squares[index] != Square::Empty {
+                return Err(error(format!("{} is already taken", word)));
+            }
+            squares[index] = to_move;
+            to_move = to_move.opponent();
+            moves.push(index);
+        }
+    }
+    Ok(moves)
+}
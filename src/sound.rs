@@ -0,0 +1,49 @@
+//! Short sound effects, made up when the game starts so that it does not need any sound files.
+
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+use sdl2::Sdl;
+
+/// The number of samples played each second.
+const SAMPLE_RATE: i32 = 44_100;
+
+/// The pitch of the tick played when the hovered square changes, in hertz.
+const HOVER_PITCH: f64 = 1_200.0;
+
+/// The length of the hover tick, in milliseconds.
+const HOVER_MILLIS: i32 = 25;
+
+/// The loudness of the hover tick, as a share of the loudest possible sound. It is kept quiet, since it plays often.
+const HOVER_VOLUME: f64 = 0.08;
+
+pub struct Sounds {
+    queue: AudioQueue<i16>,
+    hover: Vec<i16>,
+}
+
+impl Sounds {
+    /// Opens the default audio device for playing sounds.
+    pub fn open(sdl: &Sdl) -> Result<Self, String> {
+        let desired = AudioSpecDesired { freq: Some(SAMPLE_RATE), channels: Some(1), samples: None };
+        let queue = sdl.audio()?.open_queue(None, &desired)?;
+        queue.resume();
+        Ok(Self { queue, hover: tone(HOVER_PITCH, HOVER_MILLIS, HOVER_VOLUME) })
+    }
+
+    /// Plays the tick for the hovered square changing, cutting off any tick still playing.
+    pub fn play_hover(&self) {
+        self.queue.clear();
+        self.queue.queue(&self.hover);
+    }
+}
+
+/// Returns the samples of a sine wave of the given pitch, length, and volume that fades out, so it does not click.
+fn tone(pitch: f64, millis: i32, volume: f64) -> Vec<i16> {
+    let count = SAMPLE_RATE * millis / 1000;
+    (0..count)
+        .map(|n| {
+            let time = n as f64 / SAMPLE_RATE as f64;
+            let fade = 1.0 - n as f64 / count as f64;
+            ((time * pitch * std::f64::consts::TAU).sin() * fade * volume * i16::MAX as f64) as i16
+        })
+        .collect()
+}
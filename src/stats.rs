@@ -0,0 +1,146 @@
+use crate::config::Config;
+use std::time::{Duration, Instant};
+use tic_tac_toe::board::Square;
+
+/// Which moves a heatmap counts in each square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Heatmap {
+    /// Every move played.
+    Played,
+    /// Only the moves that won a game.
+    Winning,
+}
+
+/// Numbers collected over every game played since the program started.
+pub struct SessionStats {
+    started_at: Instant,
+    x_wins: u32,
+    o_wins: u32,
+    draws: u32,
+    /// The number of games in a row each player has won, up to the last one.
+    x_streak: u32,
+    o_streak: u32,
+    /// The player with the longest run of wins in the session and its length, if anyone has won.
+    longest_streak: Option<(Square, u32)>,
+    /// The number of games that were timed, which leaves out games over before a move was made.
+    timed_games: u32,
+    total_game_time: Duration,
+    /// The number of moves played in each square.
+    played: Vec<u32>,
+    /// The number of games won by a move in each square.
+    winning: Vec<u32>,
+}
+
+impl SessionStats {
+    /// Returns empty stats for a board with the given number of squares.
+    pub fn new(now: Instant, squares: usize) -> Self {
+        Self {
+            started_at: now,
+            x_wins: 0,
+            o_wins: 0,
+            draws: 0,
+            x_streak: 0,
+            o_streak: 0,
+            longest_streak: None,
+            timed_games: 0,
+            total_game_time: Duration::ZERO,
+            played: vec![0; squares],
+            winning: vec![0; squares],
+        }
+    }
+
+    /// Starts the heatmaps over for a board with the given number of squares, since moves on the old board would be
+    /// counted against the wrong squares.
+    pub fn clear_heat(&mut self, squares: usize) {
+        self.played = vec![0; squares];
+        self.winning = vec![0; squares];
+    }
+
+    /// Records the result of a finished game, where None is a draw. Losing or drawing ends a player's streak.
+    pub fn record_result(&mut self, winner: Option<Square>) {
+        match winner {
+            Some(Square::X) => {
+                self.x_wins += 1;
+                self.x_streak += 1;
+                self.o_streak = 0;
+            },
+            Some(_) => {
+                self.o_wins += 1;
+                self.o_streak += 1;
+                self.x_streak = 0;
+            },
+            None => {
+                self.draws += 1;
+                self.x_streak = 0;
+                self.o_streak = 0;
+            },
+        }
+        if let Some((winner, streak)) = self.streak() {
+            if self.longest_streak.is_none_or(|(_, longest)| streak > longest) {
+                self.longest_streak = Some((winner, streak));
+            }
+        }
+    }
+
+    /// Returns the player who won the last game and how many games in a row they have won, or None after a draw.
+    pub fn streak(&self) -> Option<(Square, u32)> {
+        if self.x_streak > 0 {
+            Some((Square::X, self.x_streak))
+        } else if self.o_streak > 0 {
+            Some((Square::O, self.o_streak))
+        } else {
+            None
+        }
+    }
+
+    /// Records how long a finished game took from its first move to its result.
+    pub fn record_game_time(&mut self, duration: Duration) {
+        self.timed_games += 1;
+        self.total_game_time += duration;
+    }
+
+    /// Records the moves of a finished game, where the last one won it if it was won on the board.
+    pub fn record_moves(&mut self, moves: &[usize], won: bool) {
+        for &square in moves {
+            self.played[square] += 1;
+        }
+        if let (true, Some(&last)) = (won, moves.last()) {
+            self.winning[last] += 1;
+        }
+    }
+
+    /// Returns the number of moves in each square that the heatmap counts.
+    pub fn heat(&self, heatmap: Heatmap) -> &[u32] {
+        match heatmap {
+            Heatmap::Played => &self.played,
+            Heatmap::Winning => &self.winning,
+        }
+    }
+
+    /// Returns the average length of the timed games, if there were any.
+    pub fn average_game_time(&self) -> Option<Duration> {
+        if self.timed_games == 0 { None } else { Some(self.total_game_time / self.timed_games) }
+    }
+
+    /// Prints the results of the session and how long it lasted.
+    pub fn print_summary(&self, now: Instant, config: &Config) {
+        let elapsed = (now - self.started_at).as_secs();
+        println!("Session summary");
+        println!("Games played: {}", self.x_wins + self.o_wins + self.draws);
+        println!(
+            "{} wins: {}, {} wins: {}, draws: {}",
+            config.name(Square::X),
+            self.x_wins,
+            config.name(Square::O),
+            self.o_wins,
+            self.draws,
+        );
+        if let Some((player, streak)) = self.streak() {
+            println!("Current win streak: {} with {}", config.name(player), streak);
+        }
+        if let Some((player, streak)) = self.longest_streak {
+            println!("Longest win streak: {} with {}", config.name(player), streak);
+        }
+        println!("Total play time: {}m {:02}s", elapsed / 60, elapsed % 60);
+    }
+}
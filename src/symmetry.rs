@@ -0,0 +1,86 @@
+//! Symmetries of the square board. Rotating or reflecting a board gives a position that plays exactly the same, so
+//! positions can be deduplicated by comparing their canonical forms.
+
+use crate::board::{board_hash, Square};
+
+/// Returns the number of squares along each side of a square board.
+fn side(squares: &[Square]) -> usize {
+    (squares.len() as f64).sqrt().round() as usize
+}
+
+/// Returns the board rotated a quarter turn clockwise.
+pub fn rotate(squares: &[Square]) -> Vec<Square> {
+    let n = side(squares);
+    (0..n * n).map(|i| squares[(n - 1 - (i % n)) * n + (i / n)]).collect()
+}
+
+/// Returns the board mirrored left to right.
+pub fn reflect(squares: &[Square]) -> Vec<Square> {
+    let n = side(squares);
+    (0..n * n).map(|i| squares[(i / n) * n + (n - 1 - (i % n))]).collect()
+}
+
+/// Returns all 8 symmetries of the board: the 4 rotations, then the 4 rotations of its mirror image.
+pub fn symmetries(squares: &[Square]) -> Vec<Vec<Square>> {
+    let mut all = Vec::with_capacity(8);
+    for start in [squares.to_vec(), reflect(squares)].iter() {
+        let mut board = start.clone();
+        for _ in 0..4 {
+            let next = rotate(&board);
+            all.push(board);
+            board = next;
+        }
+    }
+    all
+}
+
+/// Returns the same representative for every symmetry of the board: the smallest of them when compared square by square.
+pub fn canonical_board(squares: &[Square]) -> Vec<Square> {
+    symmetries(squares).into_iter().min().unwrap()
+}
+
+/// Returns the hash of the canonical form of the board, which is the same for every symmetry of it.
+pub fn canonical_hash(squares: &[Square]) -> u64 {
+    board_hash(&canonical_board(squares))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::parse_board;
+
+    #[test]
+    fn every_symmetry_has_the_same_canonical_form() {
+        for (board, size) in [("XO...X..O", 3), ("X.O.....O", 3), ("XO..X..O...X..O.", 4)] {
+            let squares = parse_board(board, size).unwrap();
+            let canonical = canonical_board(&squares);
+            for symmetry in symmetries(&squares) {
+                assert_eq!(canonical_board(&symmetry), canonical);
+                assert_eq!(canonical_hash(&symmetry), canonical_hash(&squares));
+            }
+        }
+    }
+
+    #[test]
+    fn a_board_with_no_symmetry_has_eight_different_ones() {
+        let mut all = symmetries(&parse_board("XO...X..O", 3).unwrap());
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 8);
+    }
+
+    #[test]
+    fn four_rotations_make_a_full_turn() {
+        let squares = parse_board("XO...X..O", 3).unwrap();
+        assert_eq!(rotate(&rotate(&rotate(&rotate(&squares)))), squares);
+        assert_eq!(reflect(&reflect(&squares)), squares);
+        assert_eq!(rotate(&squares), parse_board("..X..OOX.", 3).unwrap());
+    }
+
+    #[test]
+    fn different_positions_have_different_canonical_forms() {
+        let corner = parse_board("X........", 3).unwrap();
+        let edge = parse_board(".X.......", 3).unwrap();
+        assert_ne!(canonical_board(&corner), canonical_board(&edge));
+    }
+}
@@ -0,0 +1,141 @@
+use sdl2::pixels::Color;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The time between checks of whether a theme file has changed, in milliseconds.
+const THEME_POLL_MILLIS: u64 = 1000;
+
+/// The colors the game is drawn with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// The background color at the top of the window.
+    pub background_top: Color,
+    /// The background color at the bottom of the window.
+    pub background_bottom: Color,
+    pub border: Color,
+    pub playing_area: Color,
+    /// The lines between squares. Hex boards have no grid lines, only cell outlines.
+    pub grid: Color,
+    /// The outline around each square, which shows where the grid lines do not, along the edge of the board.
+    pub cell_outline: Color,
+    pub x: Color,
+    pub o: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background_top: Color::BLACK,
+            background_bottom: Color::BLACK,
+            border: Color::WHITE,
+            playing_area: Color::BLACK,
+            grid: Color::WHITE,
+            cell_outline: Color::WHITE,
+            x: Color::RED,
+            o: Color::BLUE,
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the built-in light palette: a white background with a dark grid and dark marks.
+    pub fn light() -> Self {
+        Self {
+            background_top: Color::WHITE,
+            background_bottom: Color::RGB(0xe8, 0xe8, 0xe8),
+            border: Color::RGB(0x30, 0x30, 0x30),
+            playing_area: Color::WHITE,
+            grid: Color::RGB(0x30, 0x30, 0x30),
+            cell_outline: Color::RGB(0x30, 0x30, 0x30),
+            x: Color::RGB(0xa0, 0x10, 0x20),
+            o: Color::RGB(0x10, 0x30, 0x90),
+        }
+    }
+
+    /// Loads a theme file, where each line sets a color like `border = "#ffffff"`.
+    /// Colors that are not set keep their default, and lines starting with # are comments.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read theme '{}': {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("invalid theme '{}': {}", path.display(), e))
+    }
+
+    /// Parses the contents of a theme file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut theme = Theme::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("line {}: expected key = color", number + 1))?;
+            let value = value.trim().trim_matches('"');
+            let color = parse_color(value).ok_or_else(|| format!("line {}: '{}' is not a #rrggbb color", number + 1, value))?;
+            match key.trim() {
+                "background_top" => theme.background_top = color,
+                "background_bottom" => theme.background_bottom = color,
+                "border" => theme.border = color,
+                "playing_area" => theme.playing_area = color,
+                "grid" => theme.grid = color,
+                "cell_outline" => theme.cell_outline = color,
+                "x" => theme.x = color,
+                "o" => theme.o = color,
+                other => return Err(format!("line {}: unknown color '{}'", number + 1, other)),
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// Reloads a theme file whenever it changes, found by polling its modified time.
+pub struct ThemeWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl ThemeWatcher {
+    pub fn new(path: &Path, now: Instant) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified_time(path),
+            next_check: now + Duration::from_millis(THEME_POLL_MILLIS),
+        }
+    }
+
+    /// Returns the reloaded theme, or the error loading it, if the file changed since the last check.
+    /// A file that cannot be found is left alone, since editors often replace files rather than writing to them.
+    pub fn poll(&mut self, now: Instant) -> Option<Result<Theme, String>> {
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + Duration::from_millis(THEME_POLL_MILLIS);
+        let modified = modified_time(&self.path)?;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        Some(Theme::load(&self.path))
+    }
+}
+
+/// Returns the time the file was last modified, or None if it cannot be found.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Parses a color written as #rrggbb.
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::RGB(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Returns the color the given fraction of the way from one color to another.
+pub fn lerp_color(from: Color, to: Color, fraction: f64) -> Color {
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+    Color::RGBA(channel(from.r, to.r), channel(from.g, to.g), channel(from.b, to.b), channel(from.a, to.a))
+}
@@ -0,0 +1,70 @@
+//! Brief messages that slide in at the bottom of the window and fade out, for feedback that does not need to stop the game.
+
+use crate::{fill_rectangle, font, WINDOW_SIZE};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The time a toast stays on screen, in milliseconds.
+const TOAST_LIFETIME_MILLIS: u64 = 3000;
+
+/// The time a toast takes to slide in, in milliseconds.
+const TOAST_SLIDE_MILLIS: u64 = 200;
+
+/// The time a toast takes to fade out at the end of its life, in milliseconds.
+const TOAST_FADE_MILLIS: u64 = 500;
+
+/// The most toasts shown at once. Older ones are dropped to make room.
+const MAX_TOASTS: usize = 4;
+
+/// The size of each font pixel in a toast, in screen pixels.
+const TOAST_TEXT_SCALE: u32 = 2;
+
+/// The space between a toast's text and its edges, in pixels.
+const TOAST_PADDING: u32 = 6;
+
+struct Toast {
+    text: String,
+    created: Instant,
+}
+
+/// The toasts currently on screen, oldest first.
+#[derive(Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    /// Shows a new toast.
+    pub fn push<S: Into<String>>(&mut self, text: S, now: Instant) {
+        if self.queue.len() == MAX_TOASTS {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(Toast { text: text.into(), created: now });
+    }
+
+    /// Draws the toasts in the bottom-left corner, newest at the bottom, dropping any that have expired.
+    pub fn draw(&mut self, canvas: &mut WindowCanvas, now: Instant) {
+        let lifetime = Duration::from_millis(TOAST_LIFETIME_MILLIS);
+        self.queue.retain(|toast| now - toast.created < lifetime);
+
+        let height = font::GLYPH_HEIGHT * TOAST_TEXT_SCALE + TOAST_PADDING * 2;
+        let mut y = WINDOW_SIZE as i32 - TOAST_PADDING as i32 - height as i32;
+        for toast in self.queue.iter().rev() {
+            let age = (now - toast.created).as_millis() as f64;
+            let slide = (age / TOAST_SLIDE_MILLIS as f64).min(1.0);
+            let fade = ((TOAST_LIFETIME_MILLIS as f64 - age) / TOAST_FADE_MILLIS as f64).clamp(0.0, 1.0);
+            let alpha = |max: f64| (max * fade) as u8;
+
+            let width = font::text_width(&toast.text, TOAST_TEXT_SCALE) + TOAST_PADDING * 2;
+            let x = TOAST_PADDING as i32 - ((1.0 - slide) * (width + TOAST_PADDING) as f64) as i32;
+            fill_rectangle(canvas, Rect::new(x, y, width, height), Color::RGBA(32, 32, 32, alpha(224.0)));
+            let text_x = x + TOAST_PADDING as i32;
+            let text_y = y + TOAST_PADDING as i32;
+            font::draw_text(canvas, &toast.text, text_x, text_y, TOAST_TEXT_SCALE, Color::RGBA(255, 255, 255, alpha(255.0)));
+            y -= height as i32 + TOAST_PADDING as i32;
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Headless games between two configured players, reported game by game as CSV, for comparing how strong they are.
+
+use crate::player_name;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+use tic_tac_toe::analysis::best_move;
+use tic_tac_toe::board::{get_winner, legal_moves, next_mark, winning_lines, Rules, Square};
+use tic_tac_toe::rng::Rng;
+
+/// A way of choosing moves in a tournament.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Player {
+    /// Always plays the best move.
+    Perfect,
+    /// Plays any legal move, chosen at random.
+    Random,
+}
+
+impl Player {
+    /// Parses a player by name, or returns None if there is no such player.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "perfect" => Some(Player::Perfect),
+            "random" => Some(Player::Random),
+            _ => None,
+        }
+    }
+
+    /// Returns the square this player plays in, or None if the game is over.
+    fn choose(self, squares: &[Square], rules: &Rules, rng: &mut Rng) -> Option<usize> {
+        match self {
+            Player::Perfect => best_move(squares, next_mark(squares), rules),
+            Player::Random => {
+                let legal = legal_moves(squares, &winning_lines(rules));
+                if legal.is_empty() { None } else { Some(legal[rng.below(legal.len())]) }
+            },
+        }
+    }
+}
+
+/// Plays a game from the empty board with the given player for each mark, returning the winner and the number of moves.
+/// A game that reaches the move cap, if any, is a draw.
+fn play_game(players: [Player; 2], rules: &Rules, max_moves: Option<u32>, rng: &mut Rng) -> (Option<Square>, usize) {
+    let lines = winning_lines(rules);
+    let mut squares = vec![Square::Empty; rules.squares()];
+    let mut moves = 0;
+    loop {
+        if let Some(winner) = get_winner(&squares, &lines) {
+            return (Some(winner), moves);
+        }
+        if max_moves.is_some_and(|max_moves| moves >= max_moves as usize) {
+            return (None, moves);
+        }
+        let to_move = next_mark(&squares);
+        let player = players[if to_move == Square::X { 0 } else { 1 }];
+        match player.choose(&squares, rules, rng) {
+            Some(index) => squares[index] = to_move,
+            None => return (None, moves),
+        }
+        moves += 1;
+    }
+}
+
+/// Plays the given number of games between the Red and Blue players, who take turns moving first, writing a line
+/// for each game to the report and printing the totals.
+pub fn run(games: u32, seed: u64, rules: &Rules, red: Player, blue: Player, max_moves: Option<u32>, report: &Path) -> io::Result<()> {
+    let mut rng = Rng::new(seed);
+    let mut out = BufWriter::new(File::create(report)?);
+    writeln!(out, "game,first_player,winner,moves,duration_ms")?;
+
+    // Wins are counted by the player's color rather than by mark, since marks swap with the first move.
+    let (mut red_wins, mut blue_wins, mut draws) = (0, 0, 0);
+    for game in 1..=games {
+        let red_first = game % 2 == 1;
+        let (first, second) = if red_first { (Square::X, Square::O) } else { (Square::O, Square::X) };
+        let players = if red_first { [red, blue] } else { [blue, red] };
+
+        let started = Instant::now();
+        let (winner, moves) = play_game(players, rules, max_moves, &mut rng);
+        let duration = started.elapsed();
+
+        // The player who moves first plays X, so map the winning mark back to a color.
+        let winner = winner.map(|mark| if mark == Square::X { first } else { second });
+        let winner_name = match winner {
+            Some(Square::X) => {
+                red_wins += 1;
+                player_name(Square::X)
+            },
+            Some(_) => {
+                blue_wins += 1;
+                player_name(Square::O)
+            },
+            None => {
+                draws += 1;
+                "draw"
+            },
+        };
+        writeln!(out, "{},{},{},{},{}", game, player_name(first), winner_name, moves, duration.as_millis())?;
+    }
+    out.flush()?;
+
+    println!("Played {} games of {:?} Red against {:?} Blue (seed {})", games, red, blue, seed);
+    println!(
+        "{} wins: {}, {} wins: {}, draws: {}",
+        player_name(Square::X), red_wins, player_name(Square::O), blue_wins, draws,
+    );
+    println!("Report written to {}", report.display());
+    Ok(())
+}